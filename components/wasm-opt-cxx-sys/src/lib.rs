@@ -32,8 +32,42 @@ pub mod colors {
 
 #[cxx::bridge(namespace = "wasm_shims")]
 pub mod wasm {
+    /// How `stubImportedFunction` defines the stubbed functions.
+    #[repr(u32)]
+    enum StubKind {
+        /// Trap when called.
+        Trap,
+        /// Return zero, or nothing.
+        Zero,
+        /// Return a constant.
+        Constant,
+    }
+
+    /// The type of the constant returned by a [`StubKind::Constant`] stub.
+    #[repr(u32)]
+    enum StubConstType {
+        I32,
+        I64,
+        F32,
+        F64,
+    }
+
+    /// The result of `stubImportedFunction`.
+    #[repr(u32)]
+    enum StubResult {
+        Stubbed,
+        /// The constant doesn't match the result type.
+        TypeMismatch,
+        /// The result type has no zero value.
+        NoZeroValue,
+    }
+
     unsafe extern "C++" {
         include!("shims.h");
+
+        type StubKind;
+        type StubConstType;
+        type StubResult;
     }
 
     unsafe extern "C++" {
@@ -42,6 +76,113 @@ pub mod wasm {
         fn newModule() -> UniquePtr<Module>;
 
         fn validateWasm(wasm: Pin<&mut Module>) -> bool;
+
+        fn validateWasmWithFeatures(wasm: Pin<&mut Module>, features: u32) -> bool;
+
+        fn getModuleFeatures(wasm: &Module) -> u32;
+
+        fn setModuleFeatures(wasm: Pin<&mut Module>, features: u32);
+
+        fn getFunctionNames(wasm: &Module) -> UniquePtr<CxxVector<CxxString>>;
+
+        fn getImportedFunctionNames(wasm: &Module) -> UniquePtr<CxxVector<CxxString>>;
+
+        fn getFunctionImportNames(wasm: &Module) -> UniquePtr<CxxVector<CxxString>>;
+
+        fn getFunctionsReadingGlobal(
+            wasm: &Module,
+            global: Pin<&mut CxxString>,
+        ) -> UniquePtr<CxxVector<CxxString>>;
+
+        fn setImportName(
+            wasm: Pin<&mut Module>,
+            index: u32,
+            module: Pin<&mut CxxString>,
+            base: Pin<&mut CxxString>,
+        ) -> Result<()>;
+
+        fn setExportName(
+            wasm: Pin<&mut Module>,
+            index: u32,
+            name: Pin<&mut CxxString>,
+        ) -> Result<()>;
+
+        fn updateMaps(wasm: Pin<&mut Module>);
+
+        fn copyModule(wasm: &Module) -> UniquePtr<Module>;
+
+        fn removeExport(wasm: Pin<&mut Module>, name: Pin<&mut CxxString>);
+
+        fn stubImportedFunction(
+            wasm: Pin<&mut Module>,
+            module: Pin<&mut CxxString>,
+            base: Pin<&mut CxxString>,
+            stub: StubKind,
+            const_type: StubConstType,
+            const_bits: u64,
+        ) -> StubResult;
+    }
+
+    unsafe extern "C++" {
+        type FeatureUses;
+
+        fn getFeatureUses(wasm: &Module) -> UniquePtr<FeatureUses>;
+
+        fn getFunctions(self: &FeatureUses) -> &CxxVector<CxxString>;
+
+        fn getFeatures(self: &FeatureUses) -> &CxxVector<u32>;
+
+        fn getCounts(self: &FeatureUses) -> &CxxVector<u32>;
+    }
+
+    unsafe extern "C++" {
+        type FloatUses;
+
+        fn getFloatUses(wasm: &Module) -> UniquePtr<FloatUses>;
+
+        fn getFunctions(self: &FloatUses) -> &CxxVector<CxxString>;
+
+        fn getFloats(self: &FloatUses) -> &CxxVector<u32>;
+
+        fn getNanObservable(self: &FloatUses) -> &CxxVector<u32>;
+
+        fn getSimdNan(self: &FloatUses) -> &CxxVector<u32>;
+    }
+
+    unsafe extern "C++" {
+        type ExportList;
+
+        fn getExports(wasm: &Module) -> UniquePtr<ExportList>;
+
+        fn getNames(self: &ExportList) -> &CxxVector<CxxString>;
+
+        fn getKinds(self: &ExportList) -> &CxxVector<u32>;
+
+        fn getParams(self: &ExportList) -> &CxxVector<CxxString>;
+
+        fn getResults(self: &ExportList) -> &CxxVector<CxxString>;
+    }
+
+    unsafe extern "C++" {
+        type ImportList;
+
+        fn getImports(wasm: &Module) -> UniquePtr<ImportList>;
+
+        fn getModules(self: &ImportList) -> &CxxVector<CxxString>;
+
+        fn getBases(self: &ImportList) -> &CxxVector<CxxString>;
+
+        fn getKinds(self: &ImportList) -> &CxxVector<u32>;
+    }
+
+    unsafe extern "C++" {
+        type ModuleMerger;
+
+        fn newModuleMerger(export_mode: u32) -> UniquePtr<ModuleMerger>;
+
+        fn addModule(self: Pin<&mut ModuleMerger>, input: &Module, name: Pin<&mut CxxString>);
+
+        fn merge(self: Pin<&mut ModuleMerger>) -> Result<UniquePtr<Module>>;
     }
 
     unsafe extern "C++" {
@@ -137,6 +278,8 @@ pub mod wasm {
 
         fn setInliningOptions(self: Pin<&mut Self>, inlining: UniquePtr<InliningOptions>);
 
+        fn setIgnoreImplicitTraps(self: Pin<&mut Self>, ignoreImplicitTraps: bool);
+
         fn setTrapsNeverHappen(self: Pin<&mut Self>, ignoreTraps: bool);
 
         fn setLowMemoryUnused(self: Pin<&mut Self>, memoryUnused: bool);
@@ -145,8 +288,12 @@ pub mod wasm {
 
         fn setZeroFilledMemory(self: Pin<&mut Self>, zeroFilledMemory: bool);
 
+        fn setClosedWorld(self: Pin<&mut Self>, closedWorld: bool);
+
         fn setDebugInfo(self: Pin<&mut Self>, debugInfo: bool);
 
+        fn addPassToSkip(self: Pin<&mut Self>, pass: Pin<&mut CxxString>);
+
         fn setArguments(self: Pin<&mut Self>, key: Pin<&mut CxxString>, value: Pin<&mut CxxString>);
     }
 
@@ -188,8 +335,16 @@ pub mod wasm {
 
         fn addDefaultOptimizationPasses(self: Pin<&mut Self>);
 
+        fn addDefaultGlobalOptimizationPrePasses(self: Pin<&mut Self>);
+
+        fn addDefaultFunctionOptimizationPasses(self: Pin<&mut Self>);
+
+        fn addDefaultGlobalOptimizationPostPasses(self: Pin<&mut Self>);
+
         fn run(self: Pin<&mut Self>);
 
+        fn getPassNames(self: &Self) -> UniquePtr<CxxVector<CxxString>>;
+
         fn passRemovesDebugInfo(name: Pin<&mut CxxString>) -> bool;
     }
 
@@ -6,9 +6,14 @@
 #include "support/colors.h"
 #include "wasm-validator.h"
 #include "wasm-features.h"
+#include "ir/find_all.h"
+#include "wasm-traversal.h"
+#include "wasm-builder.h"
+#include "ir/module-utils.h"
 
 #include <stdexcept> // runtime_error
 #include <memory> // unique_ptr
+#include <map>
 
 namespace rust::behavior {
   template <typename Try, typename Fail>
@@ -41,6 +46,660 @@ namespace wasm_shims {
 
     return v.validate(wasm);
   }
+
+  // Validates as if only `features` were enabled, without printing errors.
+  bool validateWasmWithFeatures(wasm::Module& wasm, uint32_t features) {
+    wasm::WasmValidator v;
+
+    auto original = wasm.features;
+    wasm.features = wasm::FeatureSet(features);
+    bool valid = v.validate(wasm, wasm::WasmValidator::Globally | wasm::WasmValidator::Quiet);
+    wasm.features = original;
+
+    return valid;
+  }
+
+  uint32_t getModuleFeatures(const Module& wasm) {
+    return wasm.features.features;
+  }
+
+  void setModuleFeatures(Module& wasm, uint32_t features) {
+    wasm.features = wasm::FeatureSet(features);
+  }
+
+  // The number of expressions in each defined function
+  // that require each feature, as parallel vectors.
+  struct FeatureUses {
+    std::vector<std::string> functions;
+    std::vector<uint32_t> features;
+    std::vector<uint32_t> counts;
+
+    const std::vector<std::string>& getFunctions() const {
+      return functions;
+    }
+
+    const std::vector<uint32_t>& getFeatures() const {
+      return features;
+    }
+
+    const std::vector<uint32_t>& getCounts() const {
+      return counts;
+    }
+  };
+
+  struct FeatureUseScanner
+    : public wasm::PostWalker<FeatureUseScanner, wasm::UnifiedExpressionVisitor<FeatureUseScanner>> {
+    const wasm::Module& wasm;
+    std::map<uint32_t, uint32_t> counts;
+
+    FeatureUseScanner(const wasm::Module& wasm) : wasm(wasm) {}
+
+    uint32_t memoryFeatures(wasm::Name memory) {
+      uint32_t bits = 0;
+      for (auto& mem : wasm.memories) {
+        if (mem->name == memory && mem->is64()) {
+          bits |= wasm::FeatureSet::Memory64;
+        }
+      }
+      if (!wasm.memories.empty() && memory != wasm.memories[0]->name) {
+        bits |= wasm::FeatureSet::MultiMemory;
+      }
+      return bits;
+    }
+
+    void visitExpression(wasm::Expression* curr) {
+      using namespace wasm;
+
+      uint32_t bits = curr->type.getFeatures().features;
+
+      if (auto* unary = curr->dynCast<Unary>()) {
+        switch (unary->op) {
+          case ExtendS8Int32:
+          case ExtendS16Int32:
+          case ExtendS8Int64:
+          case ExtendS16Int64:
+          case ExtendS32Int64:
+            bits |= FeatureSet::SignExt;
+            break;
+          case TruncSatSFloat32ToInt32:
+          case TruncSatUFloat32ToInt32:
+          case TruncSatSFloat64ToInt32:
+          case TruncSatUFloat64ToInt32:
+          case TruncSatSFloat32ToInt64:
+          case TruncSatUFloat32ToInt64:
+          case TruncSatSFloat64ToInt64:
+          case TruncSatUFloat64ToInt64:
+            bits |= FeatureSet::TruncSat;
+            break;
+          case RelaxedTruncSVecF32x4ToVecI32x4:
+          case RelaxedTruncUVecF32x4ToVecI32x4:
+          case RelaxedTruncZeroSVecF64x2ToVecI32x4:
+          case RelaxedTruncZeroUVecF64x2ToVecI32x4:
+            bits |= FeatureSet::RelaxedSIMD;
+            break;
+          default:
+            break;
+        }
+      } else if (auto* binary = curr->dynCast<Binary>()) {
+        switch (binary->op) {
+          case RelaxedSwizzleVecI8x16:
+          case RelaxedMinVecF32x4:
+          case RelaxedMaxVecF32x4:
+          case RelaxedMinVecF64x2:
+          case RelaxedMaxVecF64x2:
+          case RelaxedQ15MulrSVecI16x8:
+          case DotI8x16I7x16SToVecI16x8:
+            bits |= FeatureSet::RelaxedSIMD;
+            break;
+          default:
+            break;
+        }
+      } else if (auto* ternary = curr->dynCast<SIMDTernary>()) {
+        bits |= FeatureSet::SIMD;
+        switch (ternary->op) {
+          case RelaxedFmaVecF32x4:
+          case RelaxedFmsVecF32x4:
+          case RelaxedFmaVecF64x2:
+          case RelaxedFmsVecF64x2:
+          case LaneselectI8x16:
+          case LaneselectI16x8:
+          case LaneselectI32x4:
+          case LaneselectI64x2:
+          case DotI8x16I7x16AddSToVecI32x4:
+            bits |= FeatureSet::RelaxedSIMD;
+            break;
+          default:
+            break;
+        }
+      } else if (auto* load = curr->dynCast<Load>()) {
+        bits |= memoryFeatures(load->memory);
+        if (load->isAtomic) {
+          bits |= FeatureSet::Atomics;
+        }
+      } else if (auto* store = curr->dynCast<Store>()) {
+        bits |= memoryFeatures(store->memory);
+        if (store->isAtomic) {
+          bits |= FeatureSet::Atomics;
+        }
+      } else if (auto* size = curr->dynCast<MemorySize>()) {
+        bits |= memoryFeatures(size->memory);
+      } else if (auto* grow = curr->dynCast<MemoryGrow>()) {
+        bits |= memoryFeatures(grow->memory);
+      } else if (auto* rmw = curr->dynCast<AtomicRMW>()) {
+        bits |= FeatureSet::Atomics | memoryFeatures(rmw->memory);
+      } else if (auto* cmpxchg = curr->dynCast<AtomicCmpxchg>()) {
+        bits |= FeatureSet::Atomics | memoryFeatures(cmpxchg->memory);
+      } else if (auto* wait = curr->dynCast<AtomicWait>()) {
+        bits |= FeatureSet::Atomics | memoryFeatures(wait->memory);
+      } else if (auto* notify = curr->dynCast<AtomicNotify>()) {
+        bits |= FeatureSet::Atomics | memoryFeatures(notify->memory);
+      } else if (curr->is<AtomicFence>()) {
+        bits |= FeatureSet::Atomics;
+      } else if (auto* init = curr->dynCast<MemoryInit>()) {
+        bits |= FeatureSet::BulkMemory | memoryFeatures(init->memory);
+      } else if (curr->is<DataDrop>()) {
+        bits |= FeatureSet::BulkMemory;
+      } else if (auto* copy = curr->dynCast<MemoryCopy>()) {
+        bits |= FeatureSet::BulkMemory | memoryFeatures(copy->destMemory) |
+                memoryFeatures(copy->sourceMemory);
+      } else if (auto* fill = curr->dynCast<MemoryFill>()) {
+        bits |= FeatureSet::BulkMemory | memoryFeatures(fill->memory);
+      } else if (auto* simdLoad = curr->dynCast<SIMDLoad>()) {
+        bits |= FeatureSet::SIMD | memoryFeatures(simdLoad->memory);
+      } else if (auto* lane = curr->dynCast<SIMDLoadStoreLane>()) {
+        bits |= FeatureSet::SIMD | memoryFeatures(lane->memory);
+      } else if (curr->is<SIMDExtract>() || curr->is<SIMDReplace>() ||
+                 curr->is<SIMDShuffle>() || curr->is<SIMDShift>()) {
+        bits |= FeatureSet::SIMD;
+      } else if (curr->is<Try>() || curr->is<Throw>() || curr->is<Rethrow>()) {
+        bits |= FeatureSet::ExceptionHandling;
+      } else if (auto* call = curr->dynCast<Call>()) {
+        if (call->isReturn) {
+          bits |= FeatureSet::TailCall;
+        }
+      } else if (auto* callIndirect = curr->dynCast<CallIndirect>()) {
+        if (callIndirect->isReturn) {
+          bits |= FeatureSet::TailCall;
+        }
+      } else if (auto* callRef = curr->dynCast<CallRef>()) {
+        if (callRef->isReturn) {
+          bits |= FeatureSet::TailCall;
+        }
+      }
+
+      for (uint32_t bit = 1; bit != 0 && bit <= FeatureSet::All; bit <<= 1) {
+        if (bits & bit) {
+          counts[bit]++;
+        }
+      }
+    }
+  };
+
+  std::unique_ptr<FeatureUses> getFeatureUses(const Module& wasm) {
+    auto uses = std::make_unique<FeatureUses>();
+    for (auto& func : wasm.functions) {
+      if (func->imported()) {
+        continue;
+      }
+      FeatureUseScanner scanner(wasm);
+      wasm::Expression* body = func->body;
+      scanner.walk(body);
+      for (auto& [feature, count] : scanner.counts) {
+        uses->functions.push_back(func->name.toString());
+        uses->features.push_back(feature);
+        uses->counts.push_back(count);
+      }
+    }
+    return uses;
+  }
+
+  // The number of scalar floating-point instructions in each defined function,
+  // of those that expose the bits of a float,
+  // and of SIMD instructions that can produce NaN lanes, as parallel vectors.
+  struct FloatUses {
+    std::vector<std::string> functions;
+    std::vector<uint32_t> floats;
+    std::vector<uint32_t> nanObservable;
+    std::vector<uint32_t> simdNan;
+
+    const std::vector<std::string>& getFunctions() const {
+      return functions;
+    }
+
+    const std::vector<uint32_t>& getFloats() const {
+      return floats;
+    }
+
+    const std::vector<uint32_t>& getNanObservable() const {
+      return nanObservable;
+    }
+
+    const std::vector<uint32_t>& getSimdNan() const {
+      return simdNan;
+    }
+  };
+
+  struct FloatUseScanner
+    : public wasm::PostWalker<FloatUseScanner, wasm::UnifiedExpressionVisitor<FloatUseScanner>> {
+    uint32_t floats = 0;
+    uint32_t nanObservable = 0;
+    uint32_t simdNan = 0;
+
+    static bool isFloat(wasm::Type type) {
+      return type == wasm::Type::f32 || type == wasm::Type::f64;
+    }
+
+    // Float lane arithmetic, whose NaN results have arbitrary bits.
+    // The lanes of a v128 are observable as integers without reinterpretation.
+    // Relaxed SIMD is classified by `FeatureUseScanner`.
+    static bool isSimdNan(wasm::UnaryOp op) {
+      using namespace wasm;
+      switch (op) {
+        case SqrtVecF32x4:
+        case CeilVecF32x4:
+        case FloorVecF32x4:
+        case TruncVecF32x4:
+        case NearestVecF32x4:
+        case SqrtVecF64x2:
+        case CeilVecF64x2:
+        case FloorVecF64x2:
+        case TruncVecF64x2:
+        case NearestVecF64x2:
+        case DemoteZeroVecF64x2ToVecF32x4:
+        case PromoteLowVecF32x4ToVecF64x2:
+          return true;
+        default:
+          return false;
+      }
+    }
+
+    static bool isSimdNan(wasm::BinaryOp op) {
+      using namespace wasm;
+      switch (op) {
+        case AddVecF32x4:
+        case SubVecF32x4:
+        case MulVecF32x4:
+        case DivVecF32x4:
+        case MinVecF32x4:
+        case MaxVecF32x4:
+        case AddVecF64x2:
+        case SubVecF64x2:
+        case MulVecF64x2:
+        case DivVecF64x2:
+        case MinVecF64x2:
+        case MaxVecF64x2:
+          return true;
+        default:
+          return false;
+      }
+    }
+
+    void visitExpression(wasm::Expression* curr) {
+      using namespace wasm;
+
+      bool isFloatOp = isFloat(curr->type);
+
+      if (auto* unary = curr->dynCast<Unary>()) {
+        if (isFloat(unary->value->type)) {
+          isFloatOp = true;
+        }
+        if (unary->op == ReinterpretFloat32 || unary->op == ReinterpretFloat64) {
+          nanObservable++;
+        }
+        if (isSimdNan(unary->op)) {
+          simdNan++;
+        }
+      } else if (auto* binary = curr->dynCast<Binary>()) {
+        if (isFloat(binary->left->type)) {
+          isFloatOp = true;
+        }
+        if (isSimdNan(binary->op)) {
+          simdNan++;
+        }
+      } else if (auto* store = curr->dynCast<Store>()) {
+        if (isFloat(store->valueType)) {
+          isFloatOp = true;
+          nanObservable++;
+        }
+      }
+
+      if (isFloatOp) {
+        floats++;
+      }
+    }
+  };
+
+  std::unique_ptr<FloatUses> getFloatUses(const Module& wasm) {
+    auto uses = std::make_unique<FloatUses>();
+    for (auto& func : wasm.functions) {
+      if (func->imported()) {
+        continue;
+      }
+      FloatUseScanner scanner;
+      wasm::Expression* body = func->body;
+      scanner.walk(body);
+      if (scanner.floats > 0 || scanner.nanObservable > 0 || scanner.simdNan > 0) {
+        uses->functions.push_back(func->name.toString());
+        uses->floats.push_back(scanner.floats);
+        uses->nanObservable.push_back(scanner.nanObservable);
+        uses->simdNan.push_back(scanner.simdNan);
+      }
+    }
+    return uses;
+  }
+
+  // The exports of a module, as parallel vectors.
+  // Function signatures are comma-separated type names.
+  struct ExportList {
+    std::vector<std::string> names;
+    std::vector<uint32_t> kinds;
+    std::vector<std::string> params;
+    std::vector<std::string> results;
+
+    const std::vector<std::string>& getNames() const {
+      return names;
+    }
+
+    const std::vector<uint32_t>& getKinds() const {
+      return kinds;
+    }
+
+    const std::vector<std::string>& getParams() const {
+      return params;
+    }
+
+    const std::vector<std::string>& getResults() const {
+      return results;
+    }
+  };
+
+  std::string typeNames(wasm::Type type) {
+    std::string names;
+    for (auto t : type) {
+      if (!names.empty()) {
+        names += ",";
+      }
+      names += t.toString();
+    }
+    return names;
+  }
+
+  std::unique_ptr<ExportList> getExports(const Module& wasm) {
+    auto exports = std::make_unique<ExportList>();
+    for (auto& exp : wasm.exports) {
+      exports->names.push_back(exp->name.toString());
+      exports->kinds.push_back(static_cast<uint32_t>(exp->kind));
+
+      // Every export gets a signature entry, to keep the vectors parallel,
+      // even if an unvalidated module exports a missing function.
+      std::string params;
+      std::string results;
+      if (exp->kind == wasm::ExternalKind::Function) {
+        for (auto& func : wasm.functions) {
+          if (func->name == exp->value) {
+            params = typeNames(func->getParams());
+            results = typeNames(func->getResults());
+            break;
+          }
+        }
+      }
+      exports->params.push_back(params);
+      exports->results.push_back(results);
+    }
+    return exports;
+  }
+
+  // The imports of a module, as parallel vectors.
+  struct ImportList {
+    std::vector<std::string> modules;
+    std::vector<std::string> bases;
+    std::vector<uint32_t> kinds;
+
+    const std::vector<std::string>& getModules() const {
+      return modules;
+    }
+
+    const std::vector<std::string>& getBases() const {
+      return bases;
+    }
+
+    const std::vector<uint32_t>& getKinds() const {
+      return kinds;
+    }
+
+    void add(const wasm::Importable& item, wasm::ExternalKind kind) {
+      if (item.imported()) {
+        modules.push_back(item.module.toString());
+        bases.push_back(item.base.toString());
+        kinds.push_back(static_cast<uint32_t>(kind));
+      }
+    }
+  };
+
+  std::unique_ptr<ImportList> getImports(const Module& wasm) {
+    auto imports = std::make_unique<ImportList>();
+    for (auto& func : wasm.functions) {
+      imports->add(*func, wasm::ExternalKind::Function);
+    }
+    for (auto& table : wasm.tables) {
+      imports->add(*table, wasm::ExternalKind::Table);
+    }
+    for (auto& memory : wasm.memories) {
+      imports->add(*memory, wasm::ExternalKind::Memory);
+    }
+    for (auto& global : wasm.globals) {
+      imports->add(*global, wasm::ExternalKind::Global);
+    }
+    for (auto& tag : wasm.tags) {
+      imports->add(*tag, wasm::ExternalKind::Tag);
+    }
+    return imports;
+  }
+
+  template <typename T>
+  void collectImported(const std::vector<std::unique_ptr<T>>& items,
+                       std::vector<wasm::Importable*>& imported) {
+    for (auto& item : items) {
+      if (item->imported()) {
+        imported.push_back(item.get());
+      }
+    }
+  }
+
+  // Renames the import at `index`, in the order of `getImports`.
+  void setImportName(Module& wasm, uint32_t index, std::string& module, std::string& base) {
+    std::vector<wasm::Importable*> imported;
+    collectImported(wasm.functions, imported);
+    collectImported(wasm.tables, imported);
+    collectImported(wasm.memories, imported);
+    collectImported(wasm.globals, imported);
+    collectImported(wasm.tags, imported);
+
+    if (index >= imported.size()) {
+      throw std::runtime_error("import index out of range");
+    }
+
+    imported[index]->module = wasm::Name(module);
+    imported[index]->base = wasm::Name(base);
+  }
+
+  // Renames the export at `index`, in the order of `getExports`.
+  // Call `updateMaps` after renaming.
+  void setExportName(Module& wasm, uint32_t index, std::string& name) {
+    if (index >= wasm.exports.size()) {
+      throw std::runtime_error("export index out of range");
+    }
+
+    wasm.exports[index]->name = wasm::Name(name);
+  }
+
+  // Shared with the Rust bridge, which checks that the definitions match.
+  enum class StubKind : uint32_t { Trap, Zero, Constant };
+  enum class StubConstType : uint32_t { I32, I64, F32, F64 };
+  enum class StubResult : uint32_t { Stubbed, TypeMismatch, NoZeroValue };
+
+  // Turns the imported functions `module.base` into defined functions,
+  // returning a constant of `constType` with the bits `constBits`
+  // for `StubKind::Constant`.
+  StubResult stubImportedFunction(Module& wasm,
+                                  std::string& module,
+                                  std::string& base,
+                                  StubKind stub,
+                                  StubConstType constType,
+                                  uint64_t constBits) {
+    wasm::Name moduleName(module);
+    wasm::Name baseName(base);
+    wasm::Builder builder(wasm);
+
+    for (auto& func : wasm.functions) {
+      if (!func->imported() || func->module != moduleName || func->base != baseName) {
+        continue;
+      }
+
+      wasm::Type results = func->getResults();
+      wasm::Expression* body;
+
+      if (stub == StubKind::Trap) {
+        body = builder.makeUnreachable();
+      } else if (stub == StubKind::Zero) {
+        if (results == wasm::Type::none) {
+          body = builder.makeNop();
+        } else if (results.isDefaultable()) {
+          body = builder.makeConstantExpression(wasm::Literal::makeZeros(results));
+        } else {
+          return StubResult::NoZeroValue;
+        }
+      } else {
+        wasm::Literal value;
+        switch (constType) {
+          case StubConstType::I32:
+            value = wasm::Literal(int32_t(constBits));
+            break;
+          case StubConstType::I64:
+            value = wasm::Literal(int64_t(constBits));
+            break;
+          case StubConstType::F32:
+            value = wasm::Literal(int32_t(constBits)).castToF32();
+            break;
+          case StubConstType::F64:
+            value = wasm::Literal(int64_t(constBits)).castToF64();
+            break;
+        }
+        if (value.type != results) {
+          return StubResult::TypeMismatch;
+        }
+        body = builder.makeConst(value);
+      }
+
+      func->module = wasm::Name();
+      func->base = wasm::Name();
+      func->body = body;
+    }
+
+    return StubResult::Stubbed;
+  }
+
+  std::unique_ptr<Module> copyModule(const Module& wasm) {
+    auto copy = std::make_unique<Module>();
+    wasm::ModuleUtils::copyModule(wasm, *copy);
+    return copy;
+  }
+
+  void removeExport(Module& wasm, std::string& name) {
+    wasm.removeExport(wasm::Name(name));
+  }
+
+  void updateMaps(Module& wasm) {
+    wasm.updateMaps();
+  }
+
+  std::unique_ptr<std::vector<std::string>> getFunctionNames(const Module& wasm) {
+    std::vector<std::string> names;
+    for (auto& func : wasm.functions) {
+      names.push_back(func->name.toString());
+    }
+    return std::make_unique<std::vector<std::string>>(names);
+  }
+
+  std::unique_ptr<std::vector<std::string>> getImportedFunctionNames(const Module& wasm) {
+    std::vector<std::string> names;
+    for (auto& func : wasm.functions) {
+      if (func->imported()) {
+        names.push_back(func->name.toString());
+      }
+    }
+    return std::make_unique<std::vector<std::string>>(names);
+  }
+
+  std::unique_ptr<std::vector<std::string>> getFunctionImportNames(const Module& wasm) {
+    std::vector<std::string> names;
+    for (auto& func : wasm.functions) {
+      if (func->imported()) {
+        names.push_back(func->module.toString() + "." + func->base.toString());
+      }
+    }
+    return std::make_unique<std::vector<std::string>>(names);
+  }
+
+  std::unique_ptr<std::vector<std::string>> getFunctionsReadingGlobal(const Module& wasm, std::string& global) {
+    wasm::Name globalName(global);
+    std::vector<std::string> names;
+    for (auto& func : wasm.functions) {
+      if (func->imported()) {
+        continue;
+      }
+      wasm::FindAll<wasm::GlobalGet> gets(func->body);
+      for (auto* get : gets.list) {
+        if (get->name == globalName) {
+          names.push_back(func->name.toString());
+          break;
+        }
+      }
+    }
+    return std::make_unique<std::vector<std::string>>(names);
+  }
+}
+
+// Defined in wasm-opt-sys, appended to Binaryen's `wasm-merge.cpp`.
+namespace wasm_merge_api {
+  std::unique_ptr<wasm::Module> mergeModules(std::vector<wasm::Module*>& inputs,
+                                             const std::vector<std::string>& names,
+                                             uint32_t exportMode);
+}
+
+namespace wasm_shims {
+  // Collects modules to merge with Binaryen's `wasm-merge` logic.
+  struct ModuleMerger {
+    std::vector<std::unique_ptr<Module>> inputs;
+    std::vector<std::string> names;
+    uint32_t exportMode;
+
+    ModuleMerger(uint32_t exportMode) : exportMode(exportMode) {}
+
+    // Adds a copy of `input`, whose imports refer to it as `name`.
+    void addModule(const Module& input, std::string& name) {
+      auto copy = std::make_unique<Module>();
+      wasm::ModuleUtils::copyModule(input, *copy);
+      inputs.push_back(std::move(copy));
+      names.push_back(name);
+    }
+
+    // Merges the added modules, removing the resolved imports.
+    //
+    // Throws on export name conflicts if `exportMode` is 0,
+    // and if imports and exports form a loop.
+    std::unique_ptr<Module> merge() {
+      std::vector<Module*> modules;
+      for (auto& input : inputs) {
+        modules.push_back(input.get());
+      }
+      return wasm_merge_api::mergeModules(modules, names, exportMode);
+    }
+  };
+
+  std::unique_ptr<ModuleMerger> newModuleMerger(uint32_t exportMode) {
+    return std::make_unique<ModuleMerger>(exportMode);
+  }
 }
 
 namespace wasm_shims {
@@ -184,6 +843,10 @@ namespace wasm_shims {
       inner.inlining = inlining->inner;
     }
     
+    void setIgnoreImplicitTraps(bool ignoreImplicitTraps) {
+      inner.ignoreImplicitTraps = ignoreImplicitTraps;
+    }
+
     void setTrapsNeverHappen(bool ignoreTraps) {
       inner.trapsNeverHappen = ignoreTraps;
     }
@@ -200,10 +863,18 @@ namespace wasm_shims {
       inner.zeroFilledMemory = zeroFilledMemory;
     }
 
+    void setClosedWorld(bool closedWorld) {
+      inner.closedWorld = closedWorld;
+    }
+
     void setDebugInfo(bool debugInfo) {
       inner.debugInfo = debugInfo;
     }
 
+    void addPassToSkip(std::string& pass) {
+      inner.passesToSkip.insert(pass);
+    }
+
     void setArguments(std::string& key, std::string& value) {
       inner.arguments[std::move(key)] = std::move(value);
     }
@@ -247,22 +918,12 @@ namespace wasm_shims {
     std::vector<uint32_t> f;
 
     f.push_back(wasm::FeatureSet::Feature::None);
-    f.push_back(wasm::FeatureSet::Feature::Atomics);
-    f.push_back(wasm::FeatureSet::Feature::MutableGlobals);
-    f.push_back(wasm::FeatureSet::Feature::TruncSat);
-    f.push_back(wasm::FeatureSet::Feature::SIMD);
-    f.push_back(wasm::FeatureSet::Feature::BulkMemory);
-    f.push_back(wasm::FeatureSet::Feature::SignExt);
-    f.push_back(wasm::FeatureSet::Feature::ExceptionHandling);
-    f.push_back(wasm::FeatureSet::Feature::TailCall);
-    f.push_back(wasm::FeatureSet::Feature::ReferenceTypes);
-    f.push_back(wasm::FeatureSet::Feature::Multivalue);
-    f.push_back(wasm::FeatureSet::Feature::GC);
-    f.push_back(wasm::FeatureSet::Feature::Memory64);
-    f.push_back(wasm::FeatureSet::Feature::RelaxedSIMD);
-    f.push_back(wasm::FeatureSet::Feature::ExtendedConst);
-    f.push_back(wasm::FeatureSet::Feature::Strings);
-    f.push_back(wasm::FeatureSet::Feature::MultiMemory);
+    // Every single-bit feature in All, in order.
+    for (uint32_t bit = 1; bit != 0 && bit <= wasm::FeatureSet::Feature::All; bit <<= 1) {
+      if (bit & wasm::FeatureSet::Feature::All) {
+        f.push_back(bit);
+      }
+    }
     // This is not part of the Rust API because it has the same value as None.
     // f.push_back(wasm::FeatureSet::Feature::MVP);
     f.push_back(wasm::FeatureSet::Feature::Default);
@@ -292,9 +953,29 @@ namespace wasm_shims {
       inner.addDefaultOptimizationPasses();
     }
 
+    void addDefaultGlobalOptimizationPrePasses() {
+      inner.addDefaultGlobalOptimizationPrePasses();
+    }
+
+    void addDefaultFunctionOptimizationPasses() {
+      inner.addDefaultFunctionOptimizationPasses();
+    }
+
+    void addDefaultGlobalOptimizationPostPasses() {
+      inner.addDefaultGlobalOptimizationPostPasses();
+    }
+
     void run() {
       inner.run();
     }
+
+    std::unique_ptr<std::vector<std::string>> getPassNames() const {
+      std::vector<std::string> names;
+      for (auto& pass : inner.passes) {
+        names.push_back(pass->name);
+      }
+      return std::make_unique<std::vector<std::string>>(names);
+    }
   };
 
   std::unique_ptr<PassRunner> newPassRunner(Module& wasm) {
@@ -341,10 +1022,12 @@ namespace wasm_shims {
       && (passOptions->inner.validateGlobally == passOptionsDefaults.validateGlobally)
       && (passOptions->inner.optimizeLevel == passOptionsDefaults.optimizeLevel) 
       && (passOptions->inner.shrinkLevel == passOptionsDefaults.shrinkLevel)
+      && (passOptions->inner.ignoreImplicitTraps == passOptionsDefaults.ignoreImplicitTraps)
       && (passOptions->inner.trapsNeverHappen == passOptionsDefaults.trapsNeverHappen)
       && (passOptions->inner.lowMemoryUnused == passOptionsDefaults.lowMemoryUnused)
       && (passOptions->inner.fastMath == passOptionsDefaults.fastMath)
       && (passOptions->inner.zeroFilledMemory == passOptionsDefaults.zeroFilledMemory)
+      && (passOptions->inner.closedWorld == passOptionsDefaults.closedWorld)
       && (passOptions->inner.debugInfo == passOptionsDefaults.debugInfo)
       // inlining fields comparison
       && (passOptions->inner.inlining.alwaysInlineMaxSize == passOptionsDefaults.inlining.alwaysInlineMaxSize)
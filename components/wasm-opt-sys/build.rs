@@ -22,13 +22,19 @@ fn main() -> anyhow::Result<()> {
 
     let tools_dir = src_dir.join("tools");
     let wasm_opt_src = tools_dir.join("wasm-opt.cpp");
-    let wasm_opt_src = get_converted_wasm_opt_cpp(&wasm_opt_src)?;
+    let wasm_opt_src = get_converted_tool_cpp(&wasm_opt_src, "wasm_opt", None)?;
 
     let wasm_intrinsics_src = get_converted_wasm_intrinsics_cpp(&src_dir)?;
 
     let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")?;
     let manifest_dir = Path::new(&manifest_dir);
+
+    let wasm_merge_src = tools_dir.join("wasm-merge.cpp");
+    let wasm_merge_api = manifest_dir.join("src/wasm-merge-api.cpp");
+    let wasm_merge_src =
+        get_converted_tool_cpp(&wasm_merge_src, "wasm_merge", Some(&wasm_merge_api))?;
     let wasm_opt_main_shim = manifest_dir.join("src/wasm-opt-main-shim.cpp");
+    let wasm_merge_main_shim = manifest_dir.join("src/wasm-merge-main-shim.cpp");
 
     create_config_header()?;
 
@@ -77,8 +83,10 @@ fn main() -> anyhow::Result<()> {
 
     builder
         .file(wasm_opt_main_shim)
+        .file(wasm_merge_main_shim)
         .files(src_files)
         .file(wasm_opt_src)
+        .file(wasm_merge_src)
         .file(wasm_intrinsics_src);
 
     #[cfg(feature = "dwarf")]
@@ -115,35 +123,60 @@ fn get_binaryen_dir() -> anyhow::Result<PathBuf> {
     }
 }
 
-/// Replaces the `main` declaration with a C ABI and a different name.
+/// Replaces the `main` declaration of a tool with a C ABI and a different name,
+/// `{name}_main_actual`.
 ///
 /// It can be called from Rust and doesn't clash with Rust's `main`.
-fn get_converted_wasm_opt_cpp(src_dir: &Path) -> anyhow::Result<PathBuf> {
-    let wasm_opt_file = File::open(src_dir)?;
-    let reader = BufReader::new(wasm_opt_file);
+///
+/// The contents of `appended_file`, if any, are appended,
+/// so they can use the tool's internals.
+fn get_converted_tool_cpp(
+    src_file: &Path,
+    name: &str,
+    appended_file: Option<&Path>,
+) -> anyhow::Result<PathBuf> {
+    let tool_file = File::open(src_file)?;
+    let reader = BufReader::new(tool_file);
 
     let output_dir = std::env::var("OUT_DIR")?;
     let output_dir = Path::new(&output_dir);
 
-    let temp_file_dir = output_dir.join("wasm_opt.cpp.temp");
+    let file_name = src_file.file_name().expect("file name");
+    let temp_file_dir = output_dir.join(format!("{}.cpp.temp", name));
     let temp_file = File::create(&temp_file_dir)?;
 
+    let main_actual = format!("extern \"C\" int {}_main_actual", name);
+
     let mut writer = BufWriter::new(temp_file);
+    let mut main_count = 0;
     for line in reader.lines() {
         let mut line = line?;
 
-        if line.contains("int main") {
-            line = line.replace("int main", "extern \"C\" int wasm_opt_main_actual");
+        if line.starts_with("int main(") {
+            line = line.replacen("int main", &main_actual, 1);
+            main_count += 1;
         }
 
         writer.write_all(line.as_bytes())?;
         writer.write_all(b"\n")?;
     }
 
-    let output_wasm_opt_file = output_dir.join("wasm-opt.cpp");
-    fs::rename(&temp_file_dir, &output_wasm_opt_file)?;
+    if main_count != 1 {
+        anyhow::bail!(
+            "expected one `int main(` line in {}, found {}",
+            src_file.display(),
+            main_count
+        );
+    }
+
+    if let Some(appended_file) = appended_file {
+        writer.write_all(&fs::read(appended_file)?)?;
+    }
+
+    let output_tool_file = output_dir.join(file_name);
+    fs::rename(&temp_file_dir, &output_tool_file)?;
 
-    Ok(output_wasm_opt_file)
+    Ok(output_tool_file)
 }
 
 fn get_src_files(src_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
@@ -0,0 +1,58 @@
+// Appended to `wasm-merge.cpp` by the build script,
+// so that the tool's merge logic, which is in an anonymous namespace,
+// can be used on modules in memory.
+//
+// The tool keeps its state in globals,
+// so merges are serialized, and the state is reset before each.
+
+#include <mutex>
+
+namespace wasm_merge_api {
+
+// Merges `inputs`, whose imports refer to them by `names`,
+// as `wasm-merge` does, returning the merged module.
+//
+// The inputs are modified.
+// `exportMode` is an `ExportMergeMode`.
+//
+// Throws on export name conflicts with `ErrorOnExportConflicts`,
+// and if imports and exports form a loop.
+std::unique_ptr<Module> mergeModules(std::vector<Module*>& inputs,
+                                     const std::vector<std::string>& names,
+                                     uint32_t exportMode) {
+  static std::mutex mutex;
+  std::lock_guard<std::mutex> lock(mutex);
+
+  ModuleUtils::clearModule(merged);
+  exportModuleMap.clear();
+  exportMergeMode = static_cast<ExportMergeMode>(exportMode);
+
+  for (Index i = 0; i < inputs.size(); i++) {
+    if (i == 0) {
+      ModuleUtils::copyModule(*inputs[i], merged);
+      for (auto& curr : merged.exports) {
+        exportModuleMap[curr.get()] = ExportInfo{names[i], curr->name};
+      }
+    } else {
+      mergeInto(*inputs[i], names[i]);
+    }
+  }
+
+  fuseImportsAndExports();
+
+  {
+    PassRunner passRunner(&merged);
+    passRunner.add("remove-unused-module-elements");
+    passRunner.run();
+  }
+
+  auto output = std::make_unique<Module>();
+  ModuleUtils::copyModule(merged, *output);
+
+  ModuleUtils::clearModule(merged);
+  exportModuleMap.clear();
+
+  return output;
+}
+
+} // namespace wasm_merge_api
@@ -0,0 +1,18 @@
+#include <cstdlib> // _Exit
+#include <stdexcept> // runtime_error
+#include <iostream>
+
+extern "C" int wasm_merge_main_actual(int argc, const char* argv[]);
+
+// A wrapper for the `wasm-merge` C++ `main` function that catches exceptions.
+//
+// See `wasm-opt-main-shim.cpp`.
+extern "C" int wasm_merge_main(int argc, const char* argv[]) {
+  try {
+    return wasm_merge_main_actual(argc, argv);
+  } catch (const std::exception &e) {
+    std::cerr << e.what() << std::endl;
+    // See comments in `Fatal` about `_Exit` and static destructors.
+    _Exit(EXIT_FAILURE);
+  }
+}
@@ -1,6 +1,12 @@
+use crate::asyncify::AsyncifyConfig;
+use crate::contract::ModuleContract;
+use crate::determinism::DeterminismConfig;
 pub use crate::features::Feature;
+use crate::metadce::MetaDceGraph;
 pub use crate::passes::Pass;
 use crate::profiles::Profile;
+use crate::rename::RenameConfig;
+use crate::stubs::ImportStubs;
 use std::collections::{HashMap, HashSet};
 
 /// Optimization options and optimization builder.
@@ -23,10 +29,83 @@ pub struct OptimizationOptions {
     pub passopts: PassOptions,
     /// The set of optimization passes to apply.
     pub passes: Passes,
+    /// An ordered pipeline of passes to apply.
+    ///
+    /// If set, this replaces [`OptimizationOptions::passes`].
+    ///
+    /// Default: `None`.
+    pub pipeline: Option<Pipeline>,
     /// The set of wasm-features.
     pub features: Features,
+    /// Instrument the module with Asyncify before the optimization passes.
+    ///
+    /// Default: `None`.
+    pub asyncify: Option<AsyncifyConfig>,
+    /// Audit the optimized module for non-deterministic instructions.
+    ///
+    /// Default: `None`.
+    pub determinism: Option<DeterminismConfig>,
+    /// Exports and imports to verify on the input and optimized modules.
+    ///
+    /// Default: `None`.
+    pub contract: Option<ModuleContract>,
+    /// Replace imported functions of the input module with stubs.
+    ///
+    /// Stubs are created before [`OptimizationOptions::rename`] is applied.
+    ///
+    /// Default: `None`.
+    pub stub_imports: Option<ImportStubs>,
+    /// Rename the imports and exports of the input module.
+    ///
+    /// Renaming happens before [`OptimizationOptions::contract`]
+    /// is checked against the input module.
+    ///
+    /// Default: `None`.
+    pub rename: Option<RenameConfig>,
+    /// Remove exports the host doesn't reach, and everything only they use,
+    /// before optimizing.
+    ///
+    /// This happens after [`OptimizationOptions::contract`]
+    /// is checked against the input module.
+    /// Exports removed by [`OptimizationOptions::export_allowlist`]
+    /// stay removed even if their node is reached.
+    ///
+    /// Default: `None`.
+    pub metadce: Option<MetaDceGraph>,
+    /// Remove all exports not in this set before optimizing,
+    /// so that the passes can remove the code only they use.
+    ///
+    /// This happens after [`OptimizationOptions::contract`]
+    /// is checked against the input module,
+    /// and before [`OptimizationOptions::metadce`],
+    /// whose graph may still name the removed exports.
+    /// Naming an export the module lacks is an error.
+    ///
+    /// Default: `None`.
+    pub export_allowlist: Option<HashSet<String>>,
+    /// Options for generating and optimizing Stack IR before writing.
+    pub stack_ir: StackIrOptions,
     /// Run passes to convergence, continuing while binary size decreases.
     pub converge: bool,
+    /// Refuse to run if [`OptimizationOptions::check`] finds errors.
+    ///
+    /// Default: `false`.
+    pub reject_invalid_options: bool,
+    /// Lower features the module uses but the configured feature set lacks.
+    ///
+    /// The module is read with all features enabled,
+    /// then the lowering passes for the unsupported features it uses
+    /// (see [`Feature::lowering_pass`]) run before the other passes,
+    /// and the module is validated against the configured feature set.
+    /// Running fails with [`OptimizationError::UnsupportedFeatures`](crate::OptimizationError::UnsupportedFeatures)
+    /// if a feature can't be lowered.
+    ///
+    /// This has no effect with [`FeatureBaseline::Detect`].
+    ///
+    /// Default: `false`.
+    pub lower_unsupported_features: bool,
+    /// Lowering that is only done when requested.
+    pub lowering: LoweringOptions,
 }
 
 /// Options for reading the unoptimized wasm module.
@@ -55,6 +134,74 @@ pub struct WriterOptions {
     /// If [`ReaderOptions::file_type`] is [`FileType::Wat`],
     /// write a wat file, otherwise write a wasm file.
     pub file_type: FileType,
+    /// Emit the target features section in the output.
+    ///
+    /// This runs the [`Pass::EmitTargetFeatures`] pass after all other passes.
+    ///
+    /// Default: `false`.
+    pub emit_target_features: bool,
+}
+
+/// Options for generating and optimizing Stack IR before writing.
+///
+/// Stack IR is an alternative to Binaryen IR that is closer to the binary
+/// format, and is used by the binary writer if it is present. Optimizing it
+/// can make the output smaller.
+///
+/// These are applied after all other passes, in the order
+/// `generate`, `optimize`, `print`, corresponding to
+/// `wasm-opt --generate-stack-ir --optimize-stack-ir --print-stack-ir`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct StackIrOptions {
+    /// Generate Stack IR.
+    ///
+    /// Default: `false`.
+    pub generate: bool,
+    /// Optimize Stack IR.
+    ///
+    /// This has no effect unless [`StackIrOptions::generate`] is also set.
+    ///
+    /// Default: `false`.
+    pub optimize: bool,
+    /// Print Stack IR to stdout.
+    ///
+    /// This has no effect unless [`StackIrOptions::generate`] is also set.
+    ///
+    /// Default: `false`.
+    pub print: bool,
+}
+
+/// Lowering that is only done when requested.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LoweringOptions {
+    /// Lower exception handling with [`Pass::StripEh`]
+    /// when the configured feature set lacks it.
+    ///
+    /// This turns `throw` into `unreachable`,
+    /// so a module that throws traps instead.
+    ///
+    /// This has no effect unless
+    /// [`OptimizationOptions::lower_unsupported_features`] is also set.
+    ///
+    /// Default: `false`.
+    pub strip_exceptions: bool,
+    /// Lower 64-bit integers to pairs of 32-bit integers,
+    /// as `wasm2js` does,
+    /// with [`Pass::RemoveNonJsOps`], [`Pass::Flatten`] and [`Pass::I64ToI32Lowering`].
+    ///
+    /// This changes the signatures of functions with `i64` parameters,
+    /// including exports.
+    /// It runs after the input module is validated.
+    ///
+    /// Default: `false`.
+    pub i64_to_i32: bool,
+    /// Lower unaligned loads and stores to aligned ones
+    /// with [`Pass::AlignmentLowering`].
+    ///
+    /// It runs after the input module is validated.
+    ///
+    /// Default: `false`.
+    pub alignment: bool,
 }
 
 /// Module format used by [`ReaderOptions`] and [`WriterOptions`].
@@ -118,6 +265,10 @@ pub struct PassOptions {
     ///
     /// The default depends on how [`OptimizationOptions`] is constructed.
     pub shrink_level: ShrinkLevel,
+    /// Assume that no surprising traps occur, e.g. from loads or division.
+    ///
+    /// Default: `false`.
+    pub ignore_implicit_traps: bool,
     /// Assume traps never happen at runtime.
     ///
     /// Default: `false`.
@@ -134,6 +285,16 @@ pub struct PassOptions {
     ///
     /// Default: `false`.
     pub zero_filled_memory: bool,
+    /// Assume code outside of the module does not inspect or interact with
+    /// GC and function references, even if they are exported.
+    ///
+    /// Default: `false`.
+    pub closed_world: bool,
+    /// Passes that are not run,
+    /// even when added individually or as part of the default passes.
+    ///
+    /// Default: empty.
+    pub passes_to_skip: HashSet<Pass>,
     /// Preserve debug info.
     ///
     /// Default: `false`.
@@ -179,6 +340,68 @@ pub struct Passes {
     pub more_passes: Vec<Pass>,
 }
 
+/// An ordered list of optimization stages.
+///
+/// Unlike [`Passes`], the default optimization passes can appear anywhere
+/// in a pipeline, and more than once, each time with their own levels.
+/// This is how `wasm-opt` interprets `-O` arguments interleaved with pass arguments,
+/// e.g. `wasm-opt --dce -O2 --inlining-optimizing -O2`.
+///
+/// As with `wasm-opt`, individual passes run with the levels of the
+/// most recent stage that sets levels,
+/// or with the levels of [`PassOptions`] if no stage before them does.
+///
+/// A pipeline can be parsed from a comma-separated list of pass names
+/// and `-O` levels, e.g. `"dce,O2,inlining-optimizing,O2"`.
+/// As with `wasm-opt`, `O0` adds no passes,
+/// but resets the levels of the passes after it.
+#[derive(Clone, Debug, Default)]
+pub struct Pipeline {
+    pub stages: Vec<Stage>,
+}
+
+/// A stage of a [`Pipeline`].
+#[derive(Clone, Debug)]
+pub enum Stage {
+    /// The default optimization passes, run with the given levels.
+    ///
+    /// The individual passes after this stage also run with these levels.
+    DefaultPasses {
+        optimize_level: OptimizeLevel,
+        shrink_level: ShrinkLevel,
+    },
+    /// One phase of the default optimization passes, run with the given levels.
+    ///
+    /// Running all three phases in order is equivalent to
+    /// [`Stage::DefaultPasses`], but other passes can run between them.
+    DefaultPhase {
+        phase: DefaultPhase,
+        optimize_level: OptimizeLevel,
+        shrink_level: ShrinkLevel,
+    },
+    /// Set the levels of the individual passes after this stage,
+    /// without running any passes.
+    ///
+    /// `O0` in a parsed pipeline is this stage with both levels 0.
+    Levels {
+        optimize_level: OptimizeLevel,
+        shrink_level: ShrinkLevel,
+    },
+    /// A single pass.
+    Pass(Pass),
+}
+
+/// A phase of Binaryen's default optimization passes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DefaultPhase {
+    /// Global passes that run before the function passes.
+    GlobalPre,
+    /// Passes that optimize each function.
+    Function,
+    /// Global passes that run after the function passes.
+    GlobalPost,
+}
+
 /// Which wasm [`Feature`]s to enable and disable.
 ///
 /// The baseline features are applied first, then
@@ -208,6 +431,14 @@ pub enum FeatureBaseline {
     /// Enables [`Feature::All`].
     /// Disables [Feature::Mvp`].
     All,
+    /// The features the input module uses,
+    /// as found by [`OptimizationOptions::detect_features`].
+    ///
+    /// The module is read with all features enabled,
+    /// and its features are then set to those it uses.
+    /// Running fails with [`OptimizationError::ValidateWasmInput`](crate::OptimizationError::ValidateWasmInput)
+    /// if the module doesn't validate with all features enabled.
+    Detect,
 }
 
 /// Constructors.
@@ -219,8 +450,20 @@ impl OptimizationOptions {
             inlining: InliningOptions::default(),
             passopts: PassOptions::default(),
             passes: Passes::default(),
+            pipeline: None,
             features: Features::default(),
+            asyncify: None,
+            determinism: None,
+            contract: None,
+            stub_imports: None,
+            rename: None,
+            metadce: None,
+            export_allowlist: None,
+            stack_ir: StackIrOptions::default(),
             converge: false,
+            reject_invalid_options: false,
+            lower_unsupported_features: false,
+            lowering: LoweringOptions::default(),
         }
     }
 
@@ -321,6 +564,13 @@ impl OptimizationOptions {
     pub fn new_opt_level_4() -> Self {
         Profile::opt_level_4().into_opts()
     }
+
+    /// Apply a [`Profile`], which may be user-defined.
+    ///
+    /// See the [`profiles`](crate::profiles) module.
+    pub fn from_profile(profile: Profile) -> Self {
+        profile.into_opts()
+    }
 }
 
 impl Default for ReaderOptions {
@@ -335,6 +585,7 @@ impl Default for WriterOptions {
     fn default() -> WriterOptions {
         WriterOptions {
             file_type: FileType::Wasm,
+            emit_target_features: false,
         }
     }
 }
@@ -358,10 +609,13 @@ impl Default for PassOptions {
             validate_globally: true,
             optimize_level: OptimizeLevel::default(),
             shrink_level: ShrinkLevel::default(),
+            ignore_implicit_traps: false,
             traps_never_happen: false,
             low_memory_unused: false,
             fast_math: false,
             zero_filled_memory: false,
+            closed_world: false,
+            passes_to_skip: HashSet::new(),
             debug_info: false,
             arguments: HashMap::<String, String>::new(),
         }
@@ -0,0 +1,214 @@
+//! Asyncify instrumentation.
+//!
+//! The [Asyncify] pass transforms a module so that it can unwind and rewind
+//! its call stack, allowing synchronous code to call asynchronous imports.
+//! It is configured with lists of imports and functions,
+//! which [`AsyncifyConfig`] builds and validates against the module.
+//!
+//! [Asyncify]: https://kripken.github.io/blog/wasm/2019/07/16/asyncify.html
+//!
+//! ```no_run
+//! use wasm_opt::OptimizationOptions;
+//! use wasm_opt::asyncify::AsyncifyConfig;
+//!
+//! let mut asyncify = AsyncifyConfig::new();
+//! asyncify.import("env", "sleep").remove("log_*");
+//!
+//! let reports = OptimizationOptions::new_optimize_for_size()
+//!     .asyncify(asyncify)
+//!     .run_with_reports("input.wasm", "output.wasm")?;
+//!
+//! for function in &reports.asyncify.unwrap_or_default().instrumented {
+//!     println!("instrumented {}", function);
+//! }
+//! # Ok::<(), wasm_opt::OptimizationError>(())
+//! ```
+
+use crate::api::Pass;
+use crate::base::Module;
+use crate::pass_args::{AsyncifyArgs, PassArgs, PassArgsError};
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// The internal name of the global that holds the asyncify state.
+const ASYNCIFY_STATE: &str = "__asyncify_state";
+
+/// A builder for the arguments of the Asyncify pass.
+///
+/// Set with [`OptimizationOptions::asyncify`](crate::OptimizationOptions::asyncify)
+/// to run Asyncify before the optimization passes,
+/// as `wasm-opt --asyncify -O` does.
+/// It also implements [`PassArgs`],
+/// for use with [`OptimizationOptions::add_pass_with_args`](crate::OptimizationOptions::add_pass_with_args),
+/// in which case it is not validated against the module.
+///
+/// Function names may contain `*` wildcards.
+/// Names without wildcards must exist in the module.
+#[derive(Clone, Debug, Default)]
+pub struct AsyncifyConfig {
+    args: AsyncifyArgs,
+}
+
+/// An error resulting from an [`AsyncifyConfig`]
+/// that doesn't match the module.
+#[derive(Error, Debug)]
+pub enum AsyncifyError {
+    /// An import is not a function import of the module.
+    #[error("Asyncify import not found in module: {name}")]
+    UnknownImport { name: String },
+    /// A function is not in the module.
+    #[error("Asyncify function not found in module: {name}")]
+    UnknownFunction { name: String },
+    /// A function in the remove-list, add-list or only-list is imported.
+    ///
+    /// Imports are configured with [`AsyncifyConfig::import`].
+    #[error("Asyncify function is imported: {name}")]
+    ImportedFunction { name: String },
+    /// The arguments are invalid.
+    #[error("Invalid Asyncify arguments")]
+    InvalidArgs(
+        #[from]
+        #[source]
+        PassArgsError,
+    ),
+}
+
+/// The result of running Asyncify,
+/// returned in [`RunReports::asyncify`](crate::RunReports::asyncify).
+#[derive(Clone, Debug, Default)]
+pub struct AsyncifyReport {
+    /// The functions that were instrumented,
+    /// not including the functions added by Asyncify.
+    pub instrumented: Vec<String>,
+}
+
+impl AsyncifyConfig {
+    /// Create a configuration in which all imports may unwind and rewind.
+    pub fn new() -> AsyncifyConfig {
+        AsyncifyConfig::default()
+    }
+
+    /// Add an import that may start an unwind or rewind.
+    pub fn import(&mut self, module: &str, base: &str) -> &mut Self {
+        self.args.imports.push(format!("{}.{}", module, base));
+        self
+    }
+
+    /// Assume no imports start an unwind or rewind.
+    pub fn ignore_imports(&mut self) -> &mut Self {
+        self.args.ignore_imports = true;
+        self
+    }
+
+    /// Assume indirect calls never unwind or rewind.
+    pub fn ignore_indirect(&mut self) -> &mut Self {
+        self.args.ignore_indirect = true;
+        self
+    }
+
+    /// Don't instrument a function.
+    pub fn remove(&mut self, function: &str) -> &mut Self {
+        self.args.remove_list.push(function.to_string());
+        self
+    }
+
+    /// Instrument a function, in addition to those found by analysis.
+    pub fn add(&mut self, function: &str) -> &mut Self {
+        self.args.add_list.push(function.to_string());
+        self
+    }
+
+    /// Only instrument this function, and others passed to `only`.
+    ///
+    /// Can't be used together with [`AsyncifyConfig::remove`] or [`AsyncifyConfig::add`].
+    pub fn only(&mut self, function: &str) -> &mut Self {
+        self.args.only_list.push(function.to_string());
+        self
+    }
+
+    /// Add assertions that unwinding doesn't happen where it is not expected.
+    pub fn asserts(&mut self, value: bool) -> &mut Self {
+        self.args.asserts = value;
+        self
+    }
+
+    /// Import the asyncify state globals, for relocatable modules.
+    pub fn relocatable(&mut self, value: bool) -> &mut Self {
+        self.args.relocatable = value;
+        self
+    }
+
+    /// Keep the asyncify data in a new memory of this many pages,
+    /// rather than in the module's memory.
+    pub fn secondary_memory(&mut self, pages: u32) -> &mut Self {
+        self.args.in_secondary_memory = true;
+        self.args.secondary_memory_size = Some(pages);
+        self
+    }
+
+    /// The pass arguments this configuration produces.
+    pub fn args(&self) -> &AsyncifyArgs {
+        &self.args
+    }
+
+    /// Check the configuration against a module,
+    /// as Binaryen would when running Asyncify on it.
+    pub(crate) fn validate(&self, m: &Module) -> Result<(), AsyncifyError> {
+        self.args.to_arguments()?;
+
+        let args = &self.args;
+
+        let imports: HashSet<String> = m.function_import_names().into_iter().collect();
+        for name in args.imports.iter().filter(|name| !name.contains('*')) {
+            if !imports.contains(name) {
+                return Err(AsyncifyError::UnknownImport { name: name.clone() });
+            }
+        }
+
+        let functions: HashSet<String> = m.function_names().into_iter().collect();
+        let imported: HashSet<String> = m.imported_function_names().into_iter().collect();
+        let lists = [&args.remove_list, &args.add_list, &args.only_list];
+        for name in lists.iter().flat_map(|list| list.iter()) {
+            if name.contains('*') {
+                continue;
+            }
+            if !functions.contains(name) {
+                return Err(AsyncifyError::UnknownFunction { name: name.clone() });
+            }
+            if imported.contains(name) {
+                return Err(AsyncifyError::ImportedFunction { name: name.clone() });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PassArgs for AsyncifyConfig {
+    fn pass(&self) -> Pass {
+        Pass::Asyncify
+    }
+
+    fn to_arguments(&self) -> Result<Vec<(String, String)>, PassArgsError> {
+        self.args.to_arguments()
+    }
+}
+
+impl AsyncifyReport {
+    /// Find the functions of the input module that read the asyncify state,
+    /// after instrumentation.
+    pub(crate) fn from_module(m: &Module, input_functions: &[String]) -> AsyncifyReport {
+        let instrumented: HashSet<String> = m
+            .functions_reading_global(ASYNCIFY_STATE)
+            .into_iter()
+            .collect();
+
+        AsyncifyReport {
+            instrumented: input_functions
+                .iter()
+                .filter(|name| instrumented.contains(*name))
+                .cloned()
+                .collect(),
+        }
+    }
+}
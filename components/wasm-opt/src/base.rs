@@ -2,6 +2,8 @@ use wasm_opt_cxx_sys as wocxx;
 use wocxx::cxx::let_cxx_string;
 use wocxx::{colors, cxx, wasm};
 
+pub use wocxx::wasm::{StubConstType, StubKind, StubResult};
+
 use std::path::Path;
 use strum_macros::EnumIter;
 
@@ -18,6 +20,258 @@ impl Module {
     pub fn apply_features(&mut self, enabled_features: FeatureSet, disabled_features: FeatureSet) {
         wasm::applyFeatures(self.0.pin_mut(), enabled_features.0, disabled_features.0);
     }
+
+    /// The bits of the module's feature set.
+    pub fn features(&self) -> u32 {
+        wasm::getModuleFeatures(&self.0)
+    }
+
+    /// Replaces the module's feature set.
+    pub fn set_features(&mut self, features: u32) {
+        wasm::setModuleFeatures(self.0.pin_mut(), features);
+    }
+
+    /// Validates as if only `features` were enabled,
+    /// without printing validation errors.
+    pub fn validate_with_features(&mut self, features: u32) -> bool {
+        wasm::validateWasmWithFeatures(self.0.pin_mut(), features)
+    }
+
+    /// The number of expressions in each defined function
+    /// that require each feature.
+    pub fn feature_uses(&self) -> Vec<FeatureUse> {
+        let uses = wasm::getFeatureUses(&self.0);
+
+        uses.getFunctions()
+            .iter()
+            .zip(uses.getFeatures().iter())
+            .zip(uses.getCounts().iter())
+            .map(|((function, feature), count)| FeatureUse {
+                function: function.to_string_lossy().into_owned(),
+                feature: *feature,
+                count: *count,
+            })
+            .collect()
+    }
+
+    /// The number of floating-point instructions in each defined function,
+    /// for functions that have any.
+    pub fn float_uses(&self) -> Vec<FloatUse> {
+        let uses = wasm::getFloatUses(&self.0);
+
+        uses.getFunctions()
+            .iter()
+            .zip(uses.getFloats().iter())
+            .zip(uses.getNanObservable().iter())
+            .zip(uses.getSimdNan().iter())
+            .map(
+                |(((function, floats), nan_observable), simd_nan)| FloatUse {
+                    function: function.to_string_lossy().into_owned(),
+                    floats: *floats,
+                    nan_observable: *nan_observable,
+                    simd_nan: *simd_nan,
+                },
+            )
+            .collect()
+    }
+
+    /// The module's exports.
+    pub fn exports(&self) -> Vec<ExportInfo> {
+        let exports = wasm::getExports(&self.0);
+
+        exports
+            .getNames()
+            .iter()
+            .zip(exports.getKinds().iter())
+            .zip(exports.getParams().iter())
+            .zip(exports.getResults().iter())
+            .map(|(((name, kind), params), results)| ExportInfo {
+                name: name.to_string_lossy().into_owned(),
+                kind: *kind,
+                params: split_type_names(params),
+                results: split_type_names(results),
+            })
+            .collect()
+    }
+
+    /// The module's imports of all kinds.
+    pub fn imports(&self) -> Vec<ImportInfo> {
+        let imports = wasm::getImports(&self.0);
+
+        imports
+            .getModules()
+            .iter()
+            .zip(imports.getBases().iter())
+            .zip(imports.getKinds().iter())
+            .map(|((module, base), kind)| ImportInfo {
+                module: module.to_string_lossy().into_owned(),
+                base: base.to_string_lossy().into_owned(),
+                kind: *kind,
+            })
+            .collect()
+    }
+
+    /// The internal names of all functions, including imports.
+    pub fn function_names(&self) -> Vec<String> {
+        convert_string_vec(wasm::getFunctionNames(&self.0))
+    }
+
+    /// The internal names of imported functions.
+    pub fn imported_function_names(&self) -> Vec<String> {
+        convert_string_vec(wasm::getImportedFunctionNames(&self.0))
+    }
+
+    /// The `module.base` names of all function imports.
+    pub fn function_import_names(&self) -> Vec<String> {
+        convert_string_vec(wasm::getFunctionImportNames(&self.0))
+    }
+
+    /// The names of defined functions that read a global.
+    pub fn functions_reading_global(&self, global: &str) -> Vec<String> {
+        let_cxx_string!(global = global);
+
+        convert_string_vec(wasm::getFunctionsReadingGlobal(&self.0, global))
+    }
+
+    /// Renames the import at `index`, in the order of [`Module::imports`].
+    pub fn set_import_name(
+        &mut self,
+        index: u32,
+        module: &str,
+        base: &str,
+    ) -> Result<(), cxx::Exception> {
+        let_cxx_string!(module = module);
+        let_cxx_string!(base = base);
+
+        wasm::setImportName(self.0.pin_mut(), index, module, base)
+    }
+
+    /// Renames the export at `index`, in the order of [`Module::exports`].
+    ///
+    /// Call [`Module::update_maps`] after renaming.
+    pub fn set_export_name(&mut self, index: u32, name: &str) -> Result<(), cxx::Exception> {
+        let_cxx_string!(name = name);
+
+        wasm::setExportName(self.0.pin_mut(), index, name)
+    }
+
+    /// Rebuilds the module's name lookup tables.
+    pub fn update_maps(&mut self) {
+        wasm::updateMaps(self.0.pin_mut());
+    }
+
+    /// A deep copy of the module.
+    pub fn copy(&self) -> Module {
+        Module(wasm::copyModule(&self.0))
+    }
+
+    pub fn remove_export(&mut self, name: &str) {
+        let_cxx_string!(name = name);
+
+        wasm::removeExport(self.0.pin_mut(), name);
+    }
+
+    /// Turns the imported functions `module.base` into defined functions.
+    ///
+    /// [`StubKind::Constant`] stubs return the constant
+    /// of `const_type` with the bits `const_bits`.
+    pub fn stub_imported_function(
+        &mut self,
+        module: &str,
+        base: &str,
+        stub: StubKind,
+        const_type: StubConstType,
+        const_bits: u64,
+    ) -> StubResult {
+        let_cxx_string!(module = module);
+        let_cxx_string!(base = base);
+
+        wasm::stubImportedFunction(self.0.pin_mut(), module, base, stub, const_type, const_bits)
+    }
+}
+
+/// The number of expressions in a function that require a feature.
+pub struct FeatureUse {
+    pub function: String,
+    /// The bit of a single [`Feature`].
+    pub feature: u32,
+    pub count: u32,
+}
+
+/// The number of floating-point instructions in a function.
+pub struct FloatUse {
+    pub function: String,
+    /// Scalar floating-point instructions.
+    pub floats: u32,
+    /// Reinterpretations and stores of floats,
+    /// which expose the bits of NaN values.
+    pub nan_observable: u32,
+    /// SIMD float lane arithmetic, excluding relaxed SIMD,
+    /// which can produce NaN lanes with arbitrary bits.
+    pub simd_nan: u32,
+}
+
+/// An export.
+pub struct ExportInfo {
+    pub name: String,
+    /// The Binaryen `ExternalKind`.
+    pub kind: u32,
+    /// The parameter type names of an exported function.
+    pub params: Vec<String>,
+    /// The result type names of an exported function.
+    pub results: Vec<String>,
+}
+
+/// An import.
+pub struct ImportInfo {
+    pub module: String,
+    pub base: String,
+    /// The Binaryen `ExternalKind`.
+    pub kind: u32,
+}
+
+fn split_type_names(names: &cxx::CxxString) -> Vec<String> {
+    let names = names.to_string_lossy();
+
+    if names.is_empty() {
+        vec![]
+    } else {
+        names.split(',').map(str::to_string).collect()
+    }
+}
+
+fn convert_string_vec(names: cxx::UniquePtr<cxx::CxxVector<cxx::CxxString>>) -> Vec<String> {
+    names
+        .iter()
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Merges modules with Binaryen's `wasm-merge` tool.
+pub struct ModuleMerger(cxx::UniquePtr<wasm::ModuleMerger>);
+
+impl ModuleMerger {
+    /// `export_mode` is the tool's `ExportMergeMode`:
+    /// exports that conflict with earlier ones are an error if 0,
+    /// renamed with a suffix if 1, and skipped if 2.
+    pub fn new(export_mode: u32) -> ModuleMerger {
+        ModuleMerger(wasm::newModuleMerger(export_mode))
+    }
+
+    /// Adds a copy of `input`, whose imports refer to it as `name`.
+    pub fn add_module(&mut self, input: &Module, name: &str) {
+        let_cxx_string!(name = name);
+
+        self.0.pin_mut().addModule(&input.0, name);
+    }
+
+    /// Merges the added modules, removing the resolved imports.
+    ///
+    /// Fails on export conflicts if `export_mode` is 0,
+    /// and if imports and exports form a loop.
+    pub fn merge(&mut self) -> Result<Module, cxx::Exception> {
+        Ok(Module(self.0.pin_mut().merge()?))
+    }
 }
 
 pub struct ModuleReader(cxx::UniquePtr<wasm::ModuleReader>);
@@ -147,21 +401,41 @@ pub mod pass_registry {
         name_vec
     }
 
-    /// Aborts if `name` is invalid.
-    pub fn get_pass_description(name: &str) -> String {
+    pub fn is_registered(name: &str) -> bool {
+        get_registered_names()
+            .iter()
+            .any(|registered| registered == name)
+    }
+
+    /// Returns `None` if `name` is not registered.
+    ///
+    /// Binaryen aborts on unregistered names, so they are checked first.
+    pub fn get_pass_description(name: &str) -> Option<String> {
+        if !is_registered(name) {
+            return None;
+        }
+
         let_cxx_string!(name = name);
 
         let description = wasm::getPassDescription(name);
-        let description = description.as_ref().expect("non-null");
 
-        description.to_str().expect("utf8").to_string()
+        description
+            .as_ref()
+            .map(|description| description.to_string_lossy().into_owned())
     }
 
-    /// Aborts if `name` is invalid.
-    pub fn is_pass_hidden(name: &str) -> bool {
+    /// Returns `None` if `name` is not registered.
+    ///
+    /// Binaryen's behavior is undefined for unregistered names,
+    /// so they are checked first.
+    pub fn is_pass_hidden(name: &str) -> Option<bool> {
+        if !is_registered(name) {
+            return None;
+        }
+
         let_cxx_string!(name = name);
 
-        wasm::isPassHidden(name)
+        Some(wasm::isPassHidden(name))
     }
 }
 
@@ -230,6 +504,11 @@ impl PassOptions {
         this.setInliningOptions(inlining.0);
     }
 
+    pub fn set_ignore_implicit_traps(&mut self, ignore_implicit_traps: bool) {
+        let this = self.0.pin_mut();
+        this.setIgnoreImplicitTraps(ignore_implicit_traps);
+    }
+
     pub fn set_traps_never_happen(&mut self, ignore_traps: bool) {
         let this = self.0.pin_mut();
         this.setTrapsNeverHappen(ignore_traps);
@@ -250,11 +529,22 @@ impl PassOptions {
         this.setZeroFilledMemory(zero_filled_memory);
     }
 
+    pub fn set_closed_world(&mut self, closed_world: bool) {
+        let this = self.0.pin_mut();
+        this.setClosedWorld(closed_world);
+    }
+
     pub fn set_debug_info(&mut self, debug_info: bool) {
         let this = self.0.pin_mut();
         this.setDebugInfo(debug_info);
     }
 
+    pub fn add_pass_to_skip(&mut self, pass: &str) {
+        let this = self.0.pin_mut();
+        let_cxx_string!(pass = pass);
+        this.addPassToSkip(pass);
+    }
+
     pub fn set_arguments(&mut self, key: &str, value: &str) {
         let_cxx_string!(key = key);
         let_cxx_string!(value = value);
@@ -358,11 +648,36 @@ impl<'wasm> PassRunner<'wasm> {
         this.addDefaultOptimizationPasses();
     }
 
+    pub fn add_default_global_optimization_pre_passes(&mut self) {
+        let this = self.0.pin_mut();
+        this.addDefaultGlobalOptimizationPrePasses();
+    }
+
+    pub fn add_default_function_optimization_passes(&mut self) {
+        let this = self.0.pin_mut();
+        this.addDefaultFunctionOptimizationPasses();
+    }
+
+    pub fn add_default_global_optimization_post_passes(&mut self) {
+        let this = self.0.pin_mut();
+        this.addDefaultGlobalOptimizationPostPasses();
+    }
+
     pub fn run(&mut self) {
         let this = self.0.pin_mut();
         this.run();
     }
 
+    /// The names of the passes that have been added, in order.
+    pub fn pass_names(&self) -> Vec<String> {
+        let names = self.0.getPassNames();
+
+        names
+            .iter()
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect()
+    }
+
     pub fn pass_removes_debug_info(name: &str) -> bool {
         let_cxx_string!(name = name);
 
@@ -1,6 +1,13 @@
 //! A builder API for `OptimizationOptions`.
 
 use crate::api::*;
+use crate::asyncify::AsyncifyConfig;
+use crate::contract::ModuleContract;
+use crate::determinism::DeterminismConfig;
+use crate::metadce::MetaDceGraph;
+use crate::rename::RenameConfig;
+use crate::stubs::ImportStubs;
+use std::collections::HashSet;
 
 /// Builder methods.
 impl OptimizationOptions {
@@ -16,12 +23,123 @@ impl OptimizationOptions {
         self
     }
 
+    /// Sets [`WriterOptions::emit_target_features`].
+    pub fn emit_target_features(&mut self, value: bool) -> &mut Self {
+        self.writer.emit_target_features = value;
+        self
+    }
+
+    /// Sets [`OptimizationOptions::pipeline`].
+    pub fn pipeline(&mut self, pipeline: Pipeline) -> &mut Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// Sets [`OptimizationOptions::asyncify`].
+    pub fn asyncify(&mut self, config: AsyncifyConfig) -> &mut Self {
+        self.asyncify = Some(config);
+        self
+    }
+
+    /// Sets [`OptimizationOptions::determinism`].
+    pub fn determinism(&mut self, config: DeterminismConfig) -> &mut Self {
+        self.determinism = Some(config);
+        self
+    }
+
+    /// Sets [`OptimizationOptions::contract`].
+    pub fn contract(&mut self, contract: ModuleContract) -> &mut Self {
+        self.contract = Some(contract);
+        self
+    }
+
+    /// Sets [`OptimizationOptions::stub_imports`].
+    pub fn stub_imports(&mut self, stubs: ImportStubs) -> &mut Self {
+        self.stub_imports = Some(stubs);
+        self
+    }
+
+    /// Sets [`OptimizationOptions::rename`].
+    pub fn rename(&mut self, config: RenameConfig) -> &mut Self {
+        self.rename = Some(config);
+        self
+    }
+
+    /// Adds exports to [`OptimizationOptions::export_allowlist`].
+    ///
+    /// Once called, exports not added are removed.
+    pub fn export_allowlist<I>(&mut self, names: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let allowlist = self.export_allowlist.get_or_insert_with(HashSet::new);
+        for name in names {
+            allowlist.insert(name.as_ref().to_string());
+        }
+        self
+    }
+
+    /// Sets [`OptimizationOptions::metadce`].
+    pub fn metadce(&mut self, graph: MetaDceGraph) -> &mut Self {
+        self.metadce = Some(graph);
+        self
+    }
+
+    /// Sets [`StackIrOptions::generate`].
+    pub fn generate_stack_ir(&mut self, value: bool) -> &mut Self {
+        self.stack_ir.generate = value;
+        self
+    }
+
+    /// Sets [`StackIrOptions::optimize`].
+    pub fn optimize_stack_ir(&mut self, value: bool) -> &mut Self {
+        self.stack_ir.optimize = value;
+        self
+    }
+
+    /// Sets [`StackIrOptions::print`].
+    pub fn print_stack_ir(&mut self, value: bool) -> &mut Self {
+        self.stack_ir.print = value;
+        self
+    }
+
     /// Sets [`OptimizationOptions::converge`].
     pub fn set_converge(&mut self) -> &mut Self {
         self.converge = true;
         self
     }
 
+    /// Sets [`OptimizationOptions::reject_invalid_options`].
+    pub fn reject_invalid_options(&mut self, value: bool) -> &mut Self {
+        self.reject_invalid_options = value;
+        self
+    }
+
+    /// Sets [`OptimizationOptions::lower_unsupported_features`].
+    pub fn lower_unsupported_features(&mut self, value: bool) -> &mut Self {
+        self.lower_unsupported_features = value;
+        self
+    }
+
+    /// Sets [`LoweringOptions::strip_exceptions`].
+    pub fn strip_exceptions(&mut self, value: bool) -> &mut Self {
+        self.lowering.strip_exceptions = value;
+        self
+    }
+
+    /// Sets [`LoweringOptions::i64_to_i32`].
+    pub fn lower_i64_to_i32(&mut self, value: bool) -> &mut Self {
+        self.lowering.i64_to_i32 = value;
+        self
+    }
+
+    /// Sets [`LoweringOptions::alignment`].
+    pub fn lower_alignment(&mut self, value: bool) -> &mut Self {
+        self.lowering.alignment = value;
+        self
+    }
+
     /// Sets [`InliningOptions::always_inline_max_size`].
     pub fn always_inline_max_size(&mut self, value: u32) -> &mut Self {
         self.inlining.always_inline_max_size = value;
@@ -76,6 +194,12 @@ impl OptimizationOptions {
         self
     }
 
+    /// Sets [`PassOptions::ignore_implicit_traps`].
+    pub fn ignore_implicit_traps(&mut self, value: bool) -> &mut Self {
+        self.passopts.ignore_implicit_traps = value;
+        self
+    }
+
     /// Sets [`PassOptions::traps_never_happen`].
     pub fn traps_never_happen(&mut self, value: bool) -> &mut Self {
         self.passopts.traps_never_happen = value;
@@ -100,6 +224,18 @@ impl OptimizationOptions {
         self
     }
 
+    /// Sets [`PassOptions::closed_world`].
+    pub fn closed_world(&mut self, value: bool) -> &mut Self {
+        self.passopts.closed_world = value;
+        self
+    }
+
+    /// Adds a pass to [`PassOptions::passes_to_skip`].
+    pub fn skip_pass(&mut self, pass: Pass) -> &mut Self {
+        self.passopts.passes_to_skip.insert(pass);
+        self
+    }
+
     /// Sets [`PassOptions::debug_info`].
     pub fn debug_info(&mut self, value: bool) -> &mut Self {
         self.passopts.debug_info = value;
@@ -138,6 +274,12 @@ impl OptimizationOptions {
         self
     }
 
+    /// Sets the baseline feature set to [`FeatureBaseline::Detect`].
+    pub fn detect_feature_baseline(&mut self) -> &mut Self {
+        self.features.baseline = FeatureBaseline::Detect;
+        self
+    }
+
     /// Enables a feature.
     ///
     /// This adds the feature to [`Features::enabled`], and is equivalent to the
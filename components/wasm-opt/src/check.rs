@@ -0,0 +1,256 @@
+//! Consistency checks for `OptimizationOptions`.
+
+use crate::api::*;
+use crate::pass_args::{PassArgs, PassArgsError};
+use thiserror::Error;
+
+/// The result of [`OptimizationOptions::check`].
+#[derive(Clone, Debug, Default)]
+pub struct OptionsCheck {
+    /// Settings that are likely mistakes, but that Binaryen will accept.
+    pub warnings: Vec<OptionsIssue>,
+    /// Settings that contradict each other,
+    /// or that Binaryen will fail on.
+    pub errors: Vec<OptionsIssue>,
+}
+
+/// A problem found by [`OptimizationOptions::check`].
+#[derive(Error, Clone, Debug)]
+pub enum OptionsIssue {
+    /// A feature is both enabled and disabled.
+    ///
+    /// This is an error.
+    #[error("Feature {feature:?} is both enabled and disabled")]
+    ConflictingFeature { feature: Feature },
+    /// [`PassOptions::debug_info`] is set, but a pass removes debug info.
+    ///
+    /// This is a warning.
+    #[error("Debug info is preserved, but the `{}` pass removes it", .pass.name())]
+    DebugInfoRemoved { pass: Pass },
+    /// A pass requires a pass argument that is not set in [`PassOptions::arguments`].
+    ///
+    /// Use [`OptimizationOptions::add_pass_with_args`] to add such passes
+    /// along with their arguments.
+    ///
+    /// This is an error.
+    #[error("The `{}` pass requires the `{argument}` pass argument", .pass.name())]
+    MissingPassArgument { pass: Pass, argument: &'static str },
+    /// A [`Pass::Custom`] pass is not registered with Binaryen.
+    ///
+    /// This is an error.
+    #[error("Unknown pass: {}", .pass.name())]
+    UnknownPass { pass: Pass },
+    /// The [`AsyncifyConfig`](crate::asyncify::AsyncifyConfig) of
+    /// [`OptimizationOptions::asyncify`] can't be expressed as pass arguments.
+    ///
+    /// This is an error.
+    #[error("Invalid arguments for the `{}` pass", .pass.name())]
+    InvalidPassArgs { pass: Pass, source: PassArgsError },
+    /// Stack IR optimization or printing is requested,
+    /// but [`StackIrOptions::generate`] is not set.
+    ///
+    /// This is a warning.
+    #[error("Stack IR is optimized or printed, but not generated")]
+    StackIrNotGenerated,
+    /// [`OptimizationOptions::converge`] is set, but there are no passes to run.
+    ///
+    /// This is a warning.
+    #[error("Convergence was requested, but there are no passes to run")]
+    ConvergeWithoutPasses,
+}
+
+impl OptionsCheck {
+    /// Returns `true` if any errors were found.
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+}
+
+/// Consistency checks.
+impl OptimizationOptions {
+    /// Check the options for contradictory or incomplete settings.
+    ///
+    /// This does not load a module, and does not run Binaryen.
+    ///
+    /// [`OptimizationOptions::run`] performs these checks and returns
+    /// [`OptimizationError::InvalidOptions`](crate::OptimizationError::InvalidOptions)
+    /// on errors when [`OptimizationOptions::reject_invalid_options`] is set.
+    pub fn check(&self) -> OptionsCheck {
+        let mut check = OptionsCheck::default();
+
+        for feature in self.features.enabled.intersection(&self.features.disabled) {
+            check
+                .errors
+                .push(OptionsIssue::ConflictingFeature { feature: *feature });
+        }
+
+        let passes = self.individual_passes();
+
+        for pass in &passes {
+            if !pass.is_registered() {
+                check
+                    .errors
+                    .push(OptionsIssue::UnknownPass { pass: pass.clone() });
+            }
+        }
+
+        if let Some(asyncify) = &self.asyncify {
+            if let Err(source) = asyncify.to_arguments() {
+                check.errors.push(OptionsIssue::InvalidPassArgs {
+                    pass: Pass::Asyncify,
+                    source,
+                });
+            }
+        }
+
+        if self.passopts.debug_info {
+            for pass in &passes {
+                if pass.removes_debug_info() {
+                    check
+                        .warnings
+                        .push(OptionsIssue::DebugInfoRemoved { pass: pass.clone() });
+                }
+            }
+        }
+
+        for pass in &passes {
+            if let Some(argument) = required_pass_argument(pass) {
+                if !self.passopts.arguments.contains_key(argument) {
+                    check.errors.push(OptionsIssue::MissingPassArgument {
+                        pass: pass.clone(),
+                        argument,
+                    });
+                }
+            }
+        }
+
+        if (self.stack_ir.optimize || self.stack_ir.print) && !self.stack_ir.generate {
+            check.warnings.push(OptionsIssue::StackIrNotGenerated);
+        }
+
+        let no_passes = if let Some(pipeline) = &self.pipeline {
+            pipeline.stages.is_empty()
+        } else {
+            !self.passes.add_default_passes && self.passes.more_passes.is_empty()
+        };
+
+        if self.converge && no_passes {
+            check.warnings.push(OptionsIssue::ConvergeWithoutPasses);
+        }
+
+        check
+    }
+}
+
+/// The pass argument a pass can't run without.
+///
+/// Binaryen reads these with `getArgument`, which is fatal if the argument is missing.
+fn required_pass_argument(pass: &Pass) -> Option<&'static str> {
+    match pass {
+        Pass::ExtractFunction => Some("extract-function"),
+        Pass::ExtractFunctionIndex => Some("extract-function-index"),
+        Pass::SetGlobals => Some("set-globals"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_default_options() {
+        let opts = OptimizationOptions::new_optimize_for_size();
+        let check = opts.check();
+
+        assert!(check.warnings.is_empty());
+        assert!(check.errors.is_empty());
+    }
+
+    #[test]
+    fn test_check_conflicting_feature() {
+        let mut opts = OptimizationOptions::new_optimize_for_size();
+        opts.enable_feature(Feature::Simd)
+            .disable_feature(Feature::Simd)
+            .enable_feature(Feature::BulkMemory);
+
+        let check = opts.check();
+
+        assert!(check.has_errors());
+        assert_eq!(check.errors.len(), 1);
+        assert!(matches!(
+            check.errors[0],
+            OptionsIssue::ConflictingFeature {
+                feature: Feature::Simd
+            }
+        ));
+    }
+
+    #[test]
+    fn test_check_missing_pass_argument() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+        opts.add_pass(Pass::ExtractFunction);
+
+        let check = opts.check();
+        assert!(matches!(
+            check.errors[0],
+            OptionsIssue::MissingPassArgument {
+                pass: Pass::ExtractFunction,
+                argument: "extract-function"
+            }
+        ));
+
+        opts.set_pass_arg("extract-function", "main");
+
+        let check = opts.check();
+        assert!(!check.has_errors());
+    }
+
+    #[test]
+    fn test_check_invalid_asyncify_config() {
+        let mut asyncify = crate::asyncify::AsyncifyConfig::new();
+        asyncify.only("main").remove("sleep");
+
+        let mut opts = OptimizationOptions::new_opt_level_0();
+        opts.asyncify(asyncify);
+
+        let check = opts.check();
+        assert!(matches!(
+            check.errors[0],
+            OptionsIssue::InvalidPassArgs {
+                pass: Pass::Asyncify,
+                source: PassArgsError::Conflicting { .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_check_converge_without_passes() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+        opts.set_converge();
+
+        let check = opts.check();
+        assert!(!check.has_errors());
+        assert!(matches!(
+            check.warnings[0],
+            OptionsIssue::ConvergeWithoutPasses
+        ));
+    }
+
+    #[test]
+    fn test_check_stack_ir_not_generated() {
+        let mut opts = OptimizationOptions::new_optimize_for_size();
+        opts.optimize_stack_ir(true);
+
+        let check = opts.check();
+        assert!(matches!(
+            check.warnings[0],
+            OptionsIssue::StackIrNotGenerated
+        ));
+
+        opts.generate_stack_ir(true);
+
+        let check = opts.check();
+        assert!(check.warnings.is_empty());
+    }
+}
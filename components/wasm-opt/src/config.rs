@@ -0,0 +1,561 @@
+//! Optimizer settings declared in `Cargo.toml` package metadata.
+//!
+//! Crates that post-process their own wasm output, typically from a build
+//! script or an `xtask`, can declare their `wasm-opt` settings in their
+//! manifest instead of hardcoding an [`OptimizationOptions`]:
+//!
+//! ```toml
+//! [package.metadata.wasm-opt]
+//! opt-level = "s"
+//! passes = ["strip-debug", "strip-producers"]
+//! enable-features = ["bulk-memory"]
+//!
+//! [package.metadata.wasm-opt.profile.dev]
+//! opt-level = 0
+//!
+//! [package.metadata.wasm-opt.profile.release]
+//! opt-level = "z"
+//! converge = true
+//! ```
+//!
+//! The keys of a `profile` table override the keys of the top-level
+//! `wasm-opt` table when the named cargo profile is active.
+//!
+//! The supported keys are:
+//!
+//! - `opt-level` &middot; one of `0`, `1`, `2`, `3`, `4`, `"s"` or `"z"`,
+//!   corresponding to the `-O0` through `-Oz` arguments to `wasm-opt`.
+//!   When not specified, no default passes are run, as with `-O0`.
+//! - `optimize-level` &middot; `0` through `4`, overrides the level set by `opt-level`.
+//! - `shrink-level` &middot; `0` through `2`, overrides the level set by `opt-level`.
+//! - `passes` &middot; a list of pass names, as given on the `wasm-opt` command line,
+//!   run after the default passes.
+//! - `skip-passes` &middot; a list of pass names not to run,
+//!   as given to the `--skip-pass` command line argument.
+//! - `pipeline` &middot; a [`Pipeline`](crate::Pipeline) string,
+//!   e.g. `"dce,O2,inlining-optimizing,O2"`, which replaces
+//!   `opt-level` and `passes`.
+//! - `pass-args` &middot; a table of pass argument keys to values.
+//! - `export-allowlist` &middot; a list of export names;
+//!   all other exports are removed before optimizing.
+//! - `target` &middot; a [`Target`] name, e.g. `"browser"`,
+//!   which sets the features before the other feature keys are applied.
+//! - `features` &middot; the feature baseline, one of `"default"`, `"mvp"`, `"all"`
+//!   or `"detect"`.
+//! - `lower-unsupported-features` &middot; a boolean,
+//!   see [`OptimizationOptions::lower_unsupported_features`].
+//! - `strip-exceptions`, `lower-i64-to-i32`, `lower-alignment` &middot; booleans,
+//!   see [`LoweringOptions`](crate::LoweringOptions).
+//! - `enable-features`, `disable-features` &middot; lists of feature names,
+//!   as given to the `--enable-*` and `--disable-*` command line arguments.
+//! - `converge`, `debug-info`, `validate`, `ignore-implicit-traps`, `traps-never-happen`,
+//!   `low-memory-unused`, `fast-math`, `zero-filled-memory`, `closed-world`,
+//!   `emit-target-features`, `generate-stack-ir`,
+//!   `optimize-stack-ir`, `print-stack-ir` &middot; booleans.
+//! - `always-inline-max-function-size`, `flexible-inline-max-function-size`,
+//!   `one-caller-inline-max-function-size`, `partial-inlining-ifs` &middot; integers.
+//! - `inline-functions-with-loops` &middot; a boolean.
+//!
+//! Unknown keys are rejected, so that typos don't silently change the output.
+//!
+//! ```no_run
+//! use wasm_opt::config;
+//!
+//! let profile = config::cargo_profile_from_env();
+//! let opts = config::from_manifest("Cargo.toml", profile.as_deref())?;
+//!
+//! opts.run("hello_world.wasm", "hello_world_optimized.wasm")?;
+//!
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use crate::api::{Feature, FeatureBaseline, OptimizationOptions, OptimizeLevel, Pass, ShrinkLevel};
+use crate::passes::UnknownPassError;
+use crate::pipeline::PipelineParseError;
+use crate::profiles::Profile;
+use crate::targets::Target;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+use toml::value::Table;
+use toml::Value;
+
+/// An error resulting from loading optimizer settings from a manifest.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The manifest could not be read.
+    #[error("Failed to read manifest")]
+    Io {
+        #[source]
+        source: std::io::Error,
+    },
+    /// The manifest is not valid TOML.
+    #[error("Failed to parse manifest")]
+    Parse {
+        #[source]
+        source: toml::de::Error,
+    },
+    /// The manifest has no `[package.metadata.wasm-opt]` table.
+    #[error("The manifest has no `[package.metadata.wasm-opt]` table")]
+    MissingMetadata,
+    /// A key is not recognized.
+    #[error("Unknown `wasm-opt` metadata key: {key}")]
+    UnknownKey { key: String },
+    /// A key has a value of the wrong type or an unsupported value.
+    #[error("Invalid value for `wasm-opt` metadata key `{key}`: {value}")]
+    InvalidValue { key: String, value: String },
+    /// A pass name is not recognized.
+    #[error("Unknown pass in `wasm-opt` metadata: {name}")]
+    UnknownPass { name: String },
+    /// A feature name is not recognized.
+    #[error("Unknown feature in `wasm-opt` metadata: {name}")]
+    UnknownFeature { name: String },
+    /// A target name is not recognized.
+    #[error("Unknown target in `wasm-opt` metadata: {name}")]
+    UnknownTarget { name: String },
+}
+
+/// Load optimizer settings from the `[package.metadata.wasm-opt]` table
+/// of a `Cargo.toml` file.
+///
+/// If `cargo_profile` is provided, and the manifest contains a
+/// `[package.metadata.wasm-opt.profile.<cargo_profile>]` table,
+/// its keys override the top-level keys.
+///
+/// # Errors
+///
+/// Returns an error if the manifest can't be read or parsed,
+/// if it does not contain a `wasm-opt` metadata table,
+/// or if the table contains unknown keys or invalid values.
+pub fn from_manifest(
+    path: impl AsRef<Path>,
+    cargo_profile: Option<&str>,
+) -> Result<OptimizationOptions, Error> {
+    let manifest = fs::read_to_string(path).map_err(|e| Error::Io { source: e })?;
+    from_manifest_str(&manifest, cargo_profile)
+}
+
+/// Load optimizer settings from the contents of a `Cargo.toml` file.
+///
+/// See [`from_manifest`].
+pub fn from_manifest_str(
+    manifest: &str,
+    cargo_profile: Option<&str>,
+) -> Result<OptimizationOptions, Error> {
+    let manifest: Value = manifest.parse().map_err(|e| Error::Parse { source: e })?;
+
+    let metadata = manifest
+        .get("package")
+        .and_then(|v| v.get("metadata"))
+        .and_then(|v| v.get("wasm-opt"))
+        .ok_or(Error::MissingMetadata)?;
+    let metadata = as_table("package.metadata.wasm-opt", metadata)?;
+
+    let mut settings = metadata.clone();
+    let profiles = settings.remove("profile");
+
+    if let (Some(profiles), Some(cargo_profile)) = (profiles, cargo_profile) {
+        let profiles = as_table("profile", &profiles)?;
+        if let Some(overrides) = profiles.get(cargo_profile) {
+            let key = format!("profile.{}", cargo_profile);
+            for (key, value) in as_table(&key, overrides)? {
+                settings.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    from_table(&settings)
+}
+
+/// Get the name of the active cargo profile from the environment of a build script.
+///
+/// Cargo sets the `PROFILE` environment variable for build scripts
+/// to either `debug` or `release`.
+/// This returns the name of the corresponding cargo profile,
+/// `dev` or `release`, or `None` if `PROFILE` is not set.
+pub fn cargo_profile_from_env() -> Option<String> {
+    let profile = std::env::var("PROFILE").ok()?;
+    match profile.as_str() {
+        "debug" => Some("dev".to_string()),
+        _ => Some(profile),
+    }
+}
+
+fn from_table(table: &Table) -> Result<OptimizationOptions, Error> {
+    let mut opts = OptimizationOptions::new_opt_level_0();
+
+    // The target replaces all feature settings,
+    // so must be applied before the other feature keys.
+    if let Some(value) = table.get("target") {
+        let name = as_str("target", value)?;
+        let target = Target::from_str(name).map_err(|_| Error::UnknownTarget {
+            name: name.to_string(),
+        })?;
+        opts.target(target);
+    }
+
+    // The profile must be applied first,
+    // since it overrides the optimize and shrink levels.
+    if let Some(value) = table.get("opt-level") {
+        parse_opt_level(value)?.apply_to_opts(&mut opts);
+    }
+
+    for (key, value) in table {
+        match key.as_str() {
+            "opt-level" => { /* already applied */ }
+            "target" => { /* already applied */ }
+            "optimize-level" => {
+                opts.optimize_level(parse_optimize_level(key, value)?);
+            }
+            "shrink-level" => {
+                opts.shrink_level(parse_shrink_level(key, value)?);
+            }
+            "passes" => {
+                for name in as_str_array(key, value)? {
+                    opts.add_pass(parse_pass(name)?);
+                }
+            }
+            "skip-passes" => {
+                for name in as_str_array(key, value)? {
+                    opts.skip_pass(parse_pass(name)?);
+                }
+            }
+            "export-allowlist" => {
+                opts.export_allowlist(as_str_array(key, value)?);
+            }
+            "pipeline" => {
+                let pipeline = as_str(key, value)?.parse().map_err(|e| match e {
+                    PipelineParseError::UnknownStage { name } => Error::UnknownPass { name },
+                })?;
+                opts.pipeline(pipeline);
+            }
+            "pass-args" => {
+                for (pass_key, pass_value) in as_table(key, value)? {
+                    let pass_value = as_str(&format!("{}.{}", key, pass_key), pass_value)?;
+                    opts.set_pass_arg(pass_key, pass_value);
+                }
+            }
+            "features" => {
+                opts.features.baseline = match as_str(key, value)? {
+                    "default" => FeatureBaseline::Default,
+                    "mvp" => FeatureBaseline::MvpOnly,
+                    "all" => FeatureBaseline::All,
+                    "detect" => FeatureBaseline::Detect,
+                    _ => return Err(invalid_value(key, value)),
+                };
+            }
+            "enable-features" => {
+                for name in as_str_array(key, value)? {
+                    opts.enable_feature(parse_feature(name)?);
+                }
+            }
+            "disable-features" => {
+                for name in as_str_array(key, value)? {
+                    opts.disable_feature(parse_feature(name)?);
+                }
+            }
+            "lower-unsupported-features" => {
+                opts.lower_unsupported_features = as_bool(key, value)?;
+            }
+            "strip-exceptions" => {
+                opts.strip_exceptions(as_bool(key, value)?);
+            }
+            "lower-i64-to-i32" => {
+                opts.lower_i64_to_i32(as_bool(key, value)?);
+            }
+            "lower-alignment" => {
+                opts.lower_alignment(as_bool(key, value)?);
+            }
+            "converge" => {
+                opts.converge = as_bool(key, value)?;
+            }
+            "debug-info" => {
+                opts.debug_info(as_bool(key, value)?);
+            }
+            "validate" => {
+                opts.validate(as_bool(key, value)?);
+            }
+            "ignore-implicit-traps" => {
+                opts.ignore_implicit_traps(as_bool(key, value)?);
+            }
+            "traps-never-happen" => {
+                opts.traps_never_happen(as_bool(key, value)?);
+            }
+            "low-memory-unused" => {
+                opts.low_memory_unused(as_bool(key, value)?);
+            }
+            "fast-math" => {
+                opts.fast_math(as_bool(key, value)?);
+            }
+            "zero-filled-memory" => {
+                opts.zero_filled_memory(as_bool(key, value)?);
+            }
+            "closed-world" => {
+                opts.closed_world(as_bool(key, value)?);
+            }
+            "emit-target-features" => {
+                opts.emit_target_features(as_bool(key, value)?);
+            }
+            "generate-stack-ir" => {
+                opts.generate_stack_ir(as_bool(key, value)?);
+            }
+            "optimize-stack-ir" => {
+                opts.optimize_stack_ir(as_bool(key, value)?);
+            }
+            "print-stack-ir" => {
+                opts.print_stack_ir(as_bool(key, value)?);
+            }
+            "always-inline-max-function-size" => {
+                opts.always_inline_max_size(as_u32(key, value)?);
+            }
+            "flexible-inline-max-function-size" => {
+                opts.flexible_inline_max_size(as_u32(key, value)?);
+            }
+            "one-caller-inline-max-function-size" => {
+                opts.one_caller_inline_max_size(as_u32(key, value)?);
+            }
+            "partial-inlining-ifs" => {
+                opts.partial_inlining_ifs(as_u32(key, value)?);
+            }
+            "inline-functions-with-loops" => {
+                opts.allow_functions_with_loops(as_bool(key, value)?);
+            }
+            _ => {
+                return Err(Error::UnknownKey { key: key.clone() });
+            }
+        }
+    }
+
+    Ok(opts)
+}
+
+fn parse_opt_level(value: &Value) -> Result<Profile, Error> {
+    let level = match value {
+        Value::Integer(level) => level.to_string(),
+        Value::String(level) => level.clone(),
+        _ => return Err(invalid_value("opt-level", value)),
+    };
+
+    match level.as_str() {
+        "0" => Ok(Profile::opt_level_0()),
+        "1" => Ok(Profile::opt_level_1()),
+        "2" => Ok(Profile::opt_level_2()),
+        "3" => Ok(Profile::opt_level_3()),
+        "4" => Ok(Profile::opt_level_4()),
+        "s" => Ok(Profile::optimize_for_size()),
+        "z" => Ok(Profile::optimize_for_size_aggressively()),
+        _ => Err(invalid_value("opt-level", value)),
+    }
+}
+
+fn parse_optimize_level(key: &str, value: &Value) -> Result<OptimizeLevel, Error> {
+    match value.as_integer() {
+        Some(0) => Ok(OptimizeLevel::Level0),
+        Some(1) => Ok(OptimizeLevel::Level1),
+        Some(2) => Ok(OptimizeLevel::Level2),
+        Some(3) => Ok(OptimizeLevel::Level3),
+        Some(4) => Ok(OptimizeLevel::Level4),
+        _ => Err(invalid_value(key, value)),
+    }
+}
+
+fn parse_shrink_level(key: &str, value: &Value) -> Result<ShrinkLevel, Error> {
+    match value.as_integer() {
+        Some(0) => Ok(ShrinkLevel::Level0),
+        Some(1) => Ok(ShrinkLevel::Level1),
+        Some(2) => Ok(ShrinkLevel::Level2),
+        _ => Err(invalid_value(key, value)),
+    }
+}
+
+fn parse_pass(name: &str) -> Result<Pass, Error> {
+    name.parse::<Pass>()
+        .map_err(|UnknownPassError { name }| Error::UnknownPass { name })
+}
+
+fn parse_feature(name: &str) -> Result<Feature, Error> {
+    Feature::from_str(name).map_err(|_| Error::UnknownFeature {
+        name: name.to_string(),
+    })
+}
+
+fn as_table<'v>(key: &str, value: &'v Value) -> Result<&'v Table, Error> {
+    value.as_table().ok_or_else(|| invalid_value(key, value))
+}
+
+fn as_str<'v>(key: &str, value: &'v Value) -> Result<&'v str, Error> {
+    value.as_str().ok_or_else(|| invalid_value(key, value))
+}
+
+fn as_bool(key: &str, value: &Value) -> Result<bool, Error> {
+    value.as_bool().ok_or_else(|| invalid_value(key, value))
+}
+
+fn as_u32(key: &str, value: &Value) -> Result<u32, Error> {
+    value
+        .as_integer()
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or_else(|| invalid_value(key, value))
+}
+
+fn as_str_array<'v>(key: &str, value: &'v Value) -> Result<Vec<&'v str>, Error> {
+    let array = value.as_array().ok_or_else(|| invalid_value(key, value))?;
+    array.iter().map(|v| as_str(key, v)).collect()
+}
+
+fn invalid_value(key: &str, value: &Value) -> Error {
+    Error::InvalidValue {
+        key: key.to_string(),
+        value: value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_missing_metadata() {
+        let manifest = r#"
+            [package]
+            name = "foo"
+        "#;
+
+        let res = from_manifest_str(manifest, None);
+        assert!(matches!(res, Err(Error::MissingMetadata)));
+    }
+
+    #[test]
+    fn test_base_settings() {
+        let manifest = r#"
+            [package.metadata.wasm-opt]
+            opt-level = "z"
+            passes = ["strip-debug", "dce"]
+            skip-passes = ["vacuum"]
+            features = "mvp"
+            enable-features = ["bulk-memory"]
+            pass-args = { "extract-function" = "foo" }
+            converge = true
+            lower-unsupported-features = true
+            strip-exceptions = true
+            export-allowlist = ["main", "memory"]
+        "#;
+
+        let opts = from_manifest_str(manifest, None).unwrap();
+
+        assert_eq!(opts.passopts.optimize_level, OptimizeLevel::Level2);
+        assert_eq!(opts.passopts.shrink_level, ShrinkLevel::Level2);
+        assert!(opts.passes.add_default_passes);
+        assert_eq!(opts.passes.more_passes.len(), 2);
+        assert_eq!(opts.passes.more_passes[0].name(), "strip-debug");
+        assert_eq!(opts.passes.more_passes[1].name(), "dce");
+        assert!(opts.passopts.passes_to_skip.contains(&Pass::Vacuum));
+        assert!(matches!(opts.features.baseline, FeatureBaseline::MvpOnly));
+        assert!(opts.features.enabled.contains(&Feature::BulkMemory));
+        assert_eq!(
+            opts.passopts.arguments.get("extract-function"),
+            Some(&"foo".to_string())
+        );
+        assert!(opts.converge);
+        assert!(opts.lower_unsupported_features);
+        assert!(opts.lowering.strip_exceptions);
+        assert!(!opts.lowering.i64_to_i32);
+        assert_eq!(
+            opts.export_allowlist,
+            Some(
+                vec!["main".to_string(), "memory".to_string()]
+                    .into_iter()
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn test_profile_overrides() {
+        let manifest = r#"
+            [package.metadata.wasm-opt]
+            opt-level = 2
+            debug-info = true
+
+            [package.metadata.wasm-opt.profile.dev]
+            opt-level = 0
+
+            [package.metadata.wasm-opt.profile.release]
+            opt-level = "s"
+            shrink-level = 2
+        "#;
+
+        let opts = from_manifest_str(manifest, None).unwrap();
+        assert_eq!(opts.passopts.optimize_level, OptimizeLevel::Level2);
+        assert_eq!(opts.passopts.shrink_level, ShrinkLevel::Level0);
+        assert!(opts.passopts.debug_info);
+
+        let opts = from_manifest_str(manifest, Some("dev")).unwrap();
+        assert_eq!(opts.passopts.optimize_level, OptimizeLevel::Level0);
+        assert!(!opts.passes.add_default_passes);
+        assert!(opts.passopts.debug_info);
+
+        let opts = from_manifest_str(manifest, Some("release")).unwrap();
+        assert_eq!(opts.passopts.optimize_level, OptimizeLevel::Level2);
+        assert_eq!(opts.passopts.shrink_level, ShrinkLevel::Level2);
+        assert!(opts.passopts.debug_info);
+
+        // Profiles without overrides use the base settings.
+        let opts = from_manifest_str(manifest, Some("bench")).unwrap();
+        assert_eq!(opts.passopts.optimize_level, OptimizeLevel::Level2);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let manifest = r#"
+            [package.metadata.wasm-opt]
+            opt-level = 5
+        "#;
+        let res = from_manifest_str(manifest, None);
+        assert!(matches!(res, Err(Error::InvalidValue { .. })));
+
+        let manifest = r#"
+            [package.metadata.wasm-opt]
+            opt-levle = 2
+        "#;
+        let res = from_manifest_str(manifest, None);
+        assert!(matches!(res, Err(Error::UnknownKey { .. })));
+
+        let manifest = r#"
+            [package.metadata.wasm-opt]
+            passes = ["not-a-pass"]
+        "#;
+        let res = from_manifest_str(manifest, None);
+        assert!(matches!(res, Err(Error::UnknownPass { .. })));
+
+        let manifest = r#"
+            [package.metadata.wasm-opt]
+            enable-features = ["not-a-feature"]
+        "#;
+        let res = from_manifest_str(manifest, None);
+        assert!(matches!(res, Err(Error::UnknownFeature { .. })));
+
+        let manifest = r#"
+            [package.metadata.wasm-opt]
+            target = "toaster"
+        "#;
+        let res = from_manifest_str(manifest, None);
+        assert!(matches!(res, Err(Error::UnknownTarget { .. })));
+    }
+
+    #[test]
+    fn test_target() {
+        let manifest = r#"
+            [package.metadata.wasm-opt]
+            enable-features = ["simd"]
+            target = "deterministic"
+        "#;
+
+        let opts = from_manifest_str(manifest, None).unwrap();
+        assert!(matches!(opts.features.baseline, FeatureBaseline::MvpOnly));
+        assert!(opts.features.enabled.contains(&Feature::SignExt));
+        assert!(opts.features.enabled.contains(&Feature::Simd));
+    }
+}
@@ -0,0 +1,339 @@
+//! Verifying a module's exports and imports.
+//!
+//! A host usually calls specific exports of a module,
+//! with specific signatures, and provides a fixed set of imports.
+//! A [`ModuleContract`] declares these requirements.
+//! Set with [`OptimizationOptions::contract`](crate::OptimizationOptions::contract),
+//! it is checked against both the input module and the optimized module,
+//! so optimization can't remove an entry point or introduce an import
+//! the host doesn't provide.
+//!
+//! ```no_run
+//! use wasm_opt::OptimizationOptions;
+//! use wasm_opt::contract::{ModuleContract, ValType};
+//!
+//! let mut contract = ModuleContract::new();
+//! contract
+//!     .require_function_export("main", &[ValType::I32, ValType::I32], &[ValType::I32])
+//!     .require_export("memory")
+//!     .allow_import("env", "*");
+//!
+//! OptimizationOptions::new_optimize_for_size()
+//!     .contract(contract)
+//!     .run("input.wasm", "output.wasm")?;
+//! # Ok::<(), wasm_opt::OptimizationError>(())
+//! ```
+
+use crate::base::Module;
+use std::fmt;
+use thiserror::Error;
+
+/// Required exports and allowed imports.
+///
+/// Set with [`OptimizationOptions::contract`](crate::OptimizationOptions::contract).
+///
+/// By default there are no required exports and all imports are allowed.
+///
+/// The input module is checked after imports are stubbed and exports renamed;
+/// see [`ContractStage::Input`].
+#[derive(Clone, Debug, Default)]
+pub struct ModuleContract {
+    exports: Vec<RequiredExport>,
+    imports: Option<Vec<AllowedImport>>,
+}
+
+#[derive(Clone, Debug)]
+struct RequiredExport {
+    name: String,
+    signature: Option<Signature>,
+}
+
+#[derive(Clone, Debug)]
+struct AllowedImport {
+    module: String,
+    base: String,
+}
+
+/// A function signature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Signature {
+    /// The parameter types.
+    pub params: Vec<ValType>,
+    /// The result types.
+    pub results: Vec<ValType>,
+}
+
+/// A value type in a [`Signature`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ValType {
+    I32,
+    I64,
+    F32,
+    F64,
+    V128,
+    FuncRef,
+    ExternRef,
+}
+
+/// The kind of an export or import.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ExternalKind {
+    Function,
+    Table,
+    Memory,
+    Global,
+    Tag,
+}
+
+/// Whether a contract was checked against the input module or the optimized module.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ContractStage {
+    /// The module before optimization.
+    ///
+    /// This is checked after
+    /// [`OptimizationOptions::stub_imports`](crate::OptimizationOptions::stub_imports) and
+    /// [`OptimizationOptions::rename`](crate::OptimizationOptions::rename) are applied,
+    /// so the contract names the exports and imports the host sees.
+    Input,
+    /// The module after optimization.
+    Output,
+}
+
+/// A way in which a module doesn't satisfy a [`ModuleContract`].
+#[derive(Error, Clone, Debug, Eq, PartialEq)]
+pub enum ContractViolation {
+    /// A required export is missing.
+    #[error("Missing required export: {name}")]
+    MissingExport { name: String },
+    /// A required function export is not a function.
+    #[error("Required export {name} is a {kind}, not a function")]
+    NotAFunction { name: String, kind: ExternalKind },
+    /// A required function export has a different signature.
+    #[error("Required export {name} has signature {found}, expected {expected}")]
+    SignatureMismatch {
+        name: String,
+        expected: Signature,
+        found: String,
+    },
+    /// An import is not allowed.
+    #[error("Import not allowed: {module}.{base}")]
+    DisallowedImport {
+        module: String,
+        base: String,
+        kind: ExternalKind,
+    },
+}
+
+impl ModuleContract {
+    /// Create a contract with no requirements.
+    pub fn new() -> ModuleContract {
+        ModuleContract::default()
+    }
+
+    /// Require an export of any kind.
+    pub fn require_export(&mut self, name: &str) -> &mut Self {
+        self.exports.push(RequiredExport {
+            name: name.to_string(),
+            signature: None,
+        });
+        self
+    }
+
+    /// Require a function export with an exact signature.
+    pub fn require_function_export(
+        &mut self,
+        name: &str,
+        params: &[ValType],
+        results: &[ValType],
+    ) -> &mut Self {
+        self.exports.push(RequiredExport {
+            name: name.to_string(),
+            signature: Some(Signature {
+                params: params.to_vec(),
+                results: results.to_vec(),
+            }),
+        });
+        self
+    }
+
+    /// Allow an import.
+    ///
+    /// The `base` may be `*` to allow all imports from `module`.
+    ///
+    /// Once any import is allowed, all other imports are disallowed.
+    pub fn allow_import(&mut self, module: &str, base: &str) -> &mut Self {
+        self.imports
+            .get_or_insert_with(Vec::new)
+            .push(AllowedImport {
+                module: module.to_string(),
+                base: base.to_string(),
+            });
+        self
+    }
+
+    /// Disallow all imports not allowed by [`ModuleContract::allow_import`].
+    ///
+    /// This is only needed to disallow all imports.
+    pub fn restrict_imports(&mut self) -> &mut Self {
+        self.imports.get_or_insert_with(Vec::new);
+        self
+    }
+
+    /// Check the module against the contract.
+    pub(crate) fn check(&self, m: &Module) -> Vec<ContractViolation> {
+        let mut violations = vec![];
+
+        let exports = m.exports();
+
+        for required in &self.exports {
+            let export = match exports.iter().find(|e| e.name == required.name) {
+                Some(export) => export,
+                None => {
+                    violations.push(ContractViolation::MissingExport {
+                        name: required.name.clone(),
+                    });
+                    continue;
+                }
+            };
+
+            let signature = match &required.signature {
+                Some(signature) => signature,
+                None => continue,
+            };
+
+            let kind = ExternalKind::from_base(export.kind);
+            if kind != ExternalKind::Function {
+                violations.push(ContractViolation::NotAFunction {
+                    name: required.name.clone(),
+                    kind,
+                });
+                continue;
+            }
+
+            if !signature.matches(&export.params, &export.results) {
+                violations.push(ContractViolation::SignatureMismatch {
+                    name: required.name.clone(),
+                    expected: signature.clone(),
+                    found: format!(
+                        "({}) -> ({})",
+                        export.params.join(", "),
+                        export.results.join(", ")
+                    ),
+                });
+            }
+        }
+
+        if let Some(allowed) = &self.imports {
+            for import in m.imports() {
+                let is_allowed = allowed
+                    .iter()
+                    .any(|a| a.module == import.module && (a.base == "*" || a.base == import.base));
+
+                if !is_allowed {
+                    violations.push(ContractViolation::DisallowedImport {
+                        module: import.module,
+                        base: import.base,
+                        kind: ExternalKind::from_base(import.kind),
+                    });
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+impl Signature {
+    fn matches(&self, params: &[String], results: &[String]) -> bool {
+        fn same(types: &[ValType], names: &[String]) -> bool {
+            types.len() == names.len() && types.iter().zip(names).all(|(t, n)| t.name() == n)
+        }
+
+        same(&self.params, params) && same(&self.results, results)
+    }
+}
+
+impl ValType {
+    /// The name of the type in the text format.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValType::I32 => "i32",
+            ValType::I64 => "i64",
+            ValType::F32 => "f32",
+            ValType::F64 => "f64",
+            ValType::V128 => "v128",
+            ValType::FuncRef => "funcref",
+            ValType::ExternRef => "externref",
+        }
+    }
+}
+
+impl ExternalKind {
+    /// Convert from Binaryen's `ExternalKind`.
+    pub(crate) fn from_base(kind: u32) -> ExternalKind {
+        match kind {
+            0 => ExternalKind::Function,
+            1 => ExternalKind::Table,
+            2 => ExternalKind::Memory,
+            3 => ExternalKind::Global,
+            _ => ExternalKind::Tag,
+        }
+    }
+}
+
+impl fmt::Display for ValType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn list(types: &[ValType]) -> String {
+            types
+                .iter()
+                .map(ValType::name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+
+        write!(f, "({}) -> ({})", list(&self.params), list(&self.results))
+    }
+}
+
+impl fmt::Display for ExternalKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ExternalKind::Function => "function",
+            ExternalKind::Table => "table",
+            ExternalKind::Memory => "memory",
+            ExternalKind::Global => "global",
+            ExternalKind::Tag => "tag",
+        };
+        f.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signature_matches() {
+        let signature = Signature {
+            params: vec![ValType::I32, ValType::I64],
+            results: vec![ValType::FuncRef],
+        };
+
+        let names = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert!(signature.matches(&names(&["i32", "i64"]), &names(&["funcref"])));
+        assert!(!signature.matches(&names(&["i32"]), &names(&["funcref"])));
+        assert!(!signature.matches(&names(&["i32", "i32"]), &names(&["funcref"])));
+        assert!(!signature.matches(&names(&["i32", "i64"]), &names(&[])));
+
+        assert_eq!(signature.to_string(), "(i32, i64) -> (funcref)");
+    }
+}
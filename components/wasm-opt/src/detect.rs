@@ -0,0 +1,214 @@
+//! Detecting the wasm features a module uses.
+
+use crate::api::*;
+use crate::base::{Feature as BaseFeature, Module};
+use crate::run::OptimizationError;
+use std::path::Path;
+
+/// The features used by a module,
+/// as returned by [`OptimizationOptions::detect_features`].
+#[derive(Clone, Debug, Default)]
+pub struct DetectedFeatures {
+    /// The features the module uses,
+    /// in the order of [`Feature::all_real`].
+    pub used: Vec<Feature>,
+}
+
+impl DetectedFeatures {
+    /// Returns `true` if the module uses the feature.
+    pub fn uses(&self, feature: Feature) -> bool {
+        self.used.contains(&feature)
+    }
+
+    /// Detect the features used by a module,
+    /// by validating it with each feature disabled in turn.
+    ///
+    /// A feature is disabled together with the features that require it,
+    /// since the validator rejects e.g. GC without reference types
+    /// whatever the module contains.
+    /// So a module that uses GC is also reported to use reference types,
+    /// which GC builds on.
+    ///
+    /// Returns `None` if the module doesn't validate with all features enabled.
+    pub(crate) fn detect(m: &mut Module) -> Option<DetectedFeatures> {
+        let all = BaseFeature::All as u32;
+
+        if !m.validate_with_features(all) {
+            return None;
+        }
+
+        let used = Feature::all_real()
+            .iter()
+            .copied()
+            .filter(|feature| {
+                let disabled = feature
+                    .required_by()
+                    .iter()
+                    .fold(feature.to_base() as u32, |bits, other| {
+                        bits | other.to_base() as u32
+                    });
+                !m.validate_with_features(all & !disabled)
+            })
+            .collect();
+
+        Some(DetectedFeatures { used })
+    }
+
+    fn bits(&self) -> u32 {
+        self.used
+            .iter()
+            .fold(0, |bits, feature| bits | feature.to_base() as u32)
+    }
+}
+
+/// Where a module uses each feature,
+/// as returned by [`OptimizationOptions::audit_features`].
+///
+/// Only instructions in defined function bodies are counted.
+/// Features used elsewhere, for example by the type of a global,
+/// the index type of a memory, or a function signature,
+/// are reported by [`OptimizationOptions::detect_features`] but not here.
+#[derive(Clone, Debug, Default)]
+pub struct FeatureAudit {
+    /// The features used by instructions,
+    /// in the order of [`Feature::all_real`].
+    pub features: Vec<FeatureUsage>,
+}
+
+/// The functions that use a feature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeatureUsage {
+    /// The feature.
+    pub feature: Feature,
+    /// The functions with instructions that use the feature,
+    /// in module order.
+    pub functions: Vec<FunctionUsage>,
+}
+
+/// The number of instructions in a function that use a feature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionUsage {
+    /// The function's internal name.
+    pub function: String,
+    /// The number of instructions that use the feature.
+    ///
+    /// An instruction counts if its result type requires the feature,
+    /// as with `v128` values, or if the instruction itself does.
+    pub instructions: u32,
+}
+
+impl FeatureAudit {
+    /// The usage of a feature, or `None` if no instructions use it.
+    pub fn get(&self, feature: Feature) -> Option<&FeatureUsage> {
+        self.features.iter().find(|usage| usage.feature == feature)
+    }
+
+    pub(crate) fn from_module(m: &Module) -> FeatureAudit {
+        let uses = m.feature_uses();
+
+        let features = Feature::all_real()
+            .iter()
+            .filter_map(|feature| {
+                let bit = feature.to_base() as u32;
+                let functions: Vec<FunctionUsage> = uses
+                    .iter()
+                    .filter(|u| u.feature == bit)
+                    .map(|u| FunctionUsage {
+                        function: u.function.clone(),
+                        instructions: u.count,
+                    })
+                    .collect();
+
+                if functions.is_empty() {
+                    None
+                } else {
+                    Some(FeatureUsage {
+                        feature: *feature,
+                        functions,
+                    })
+                }
+            })
+            .collect();
+
+        FeatureAudit { features }
+    }
+}
+
+impl FeatureUsage {
+    /// The total number of instructions that use the feature.
+    pub fn instructions(&self) -> u32 {
+        self.functions.iter().map(|f| f.instructions).sum()
+    }
+}
+
+/// Feature detection.
+impl OptimizationOptions {
+    /// Detect the wasm features a module uses.
+    ///
+    /// This reads the module with all features enabled,
+    /// then finds the features it can't validate without.
+    /// A `target_features` section in the module is not consulted,
+    /// as it may declare features that are not used.
+    ///
+    /// The module is read according to [`OptimizationOptions::reader`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error on I/O failure, or if the input fails to parse.
+    /// Returns [`OptimizationError::ValidateWasmInput`] if the module
+    /// doesn't validate even with all features enabled.
+    pub fn detect_features(
+        &self,
+        infile: impl AsRef<Path>,
+    ) -> Result<DetectedFeatures, OptimizationError> {
+        let mut m = Module::new();
+        m.set_features(BaseFeature::All as u32);
+
+        self.read_module(infile.as_ref(), None, &mut m)?;
+
+        DetectedFeatures::detect(&mut m).ok_or(OptimizationError::ValidateWasmInput)
+    }
+
+    /// Report which functions use each feature,
+    /// and how many of their instructions use it.
+    ///
+    /// This reads the module with all features enabled.
+    /// Unlike [`OptimizationOptions::detect_features`]
+    /// the module is not validated.
+    ///
+    /// The module is read according to [`OptimizationOptions::reader`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error on I/O failure, or if the input fails to parse.
+    pub fn audit_features(
+        &self,
+        infile: impl AsRef<Path>,
+    ) -> Result<FeatureAudit, OptimizationError> {
+        let mut m = Module::new();
+        m.set_features(BaseFeature::All as u32);
+
+        self.read_module(infile.as_ref(), None, &mut m)?;
+
+        Ok(FeatureAudit::from_module(&m))
+    }
+
+    /// For [`FeatureBaseline::Detect`],
+    /// replace the module's features with those it uses,
+    /// then apply [`Features::enabled`] and [`Features::disabled`].
+    pub(crate) fn apply_detected_features(&self, m: &mut Module) -> Result<(), OptimizationError> {
+        let detected = DetectedFeatures::detect(m).ok_or(OptimizationError::ValidateWasmInput)?;
+
+        let mut bits = detected.bits();
+        for feature in &self.features.enabled {
+            bits |= feature.to_base() as u32;
+        }
+        for feature in &self.features.disabled {
+            bits &= !(feature.to_base() as u32);
+        }
+
+        m.set_features(bits);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+//! Auditing modules for non-deterministic behavior.
+//!
+//! Some runtimes, like blockchain virtual machines, must execute
+//! every program identically on every host, and reject modules with
+//! instructions whose results can differ between hosts:
+//! floating-point arithmetic that can produce NaNs with arbitrary bits,
+//! relaxed SIMD, and atomics with concurrent threads.
+//!
+//! Setting a [`DeterminismConfig`] with
+//! [`OptimizationOptions::determinism`](crate::OptimizationOptions::determinism)
+//! audits the optimized module before it is written.
+//!
+//! ```no_run
+//! use wasm_opt::OptimizationOptions;
+//! use wasm_opt::determinism::DeterminismConfig;
+//!
+//! let mut determinism = DeterminismConfig::new();
+//! determinism.allow_floats(true).canonicalize_nans(true);
+//!
+//! let reports = OptimizationOptions::new_optimize_for_size()
+//!     .determinism(determinism)
+//!     .run_with_reports("input.wasm", "output.wasm")?;
+//!
+//! for violation in &reports.determinism.unwrap_or_default().violations {
+//!     println!("{:?} in {}", violation.kind, violation.function);
+//! }
+//! # Ok::<(), wasm_opt::OptimizationError>(())
+//! ```
+
+use crate::api::{Feature, Pass};
+use crate::base::{Module, PassOptions, PassRunner};
+use std::fmt;
+
+/// Which non-deterministic instructions are allowed.
+///
+/// Set with [`OptimizationOptions::determinism`](crate::OptimizationOptions::determinism).
+///
+/// By default every kind of violation is reported,
+/// and the module is still written.
+#[derive(Clone, Debug, Default)]
+pub struct DeterminismConfig {
+    allow_floats: bool,
+    canonicalize_nans: bool,
+    reject: bool,
+}
+
+/// A kind of non-deterministic instruction.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ViolationKind {
+    /// A scalar floating-point instruction.
+    ///
+    /// Floating-point SIMD lane arithmetic is reported as
+    /// [`ViolationKind::SimdNan`].
+    Float,
+    /// A reinterpretation or store of a float,
+    /// which exposes the bits of a NaN.
+    NanObservable,
+    /// A floating-point SIMD lane instruction that can produce NaNs,
+    /// whose bits are observable as integer lanes.
+    ///
+    /// [`Pass::DeNan`] doesn't canonicalize SIMD lanes,
+    /// so these are reported even with [`DeterminismConfig::canonicalize_nans`].
+    SimdNan,
+    /// A relaxed SIMD instruction,
+    /// whose results are implementation-defined.
+    RelaxedSimd,
+    /// An atomic instruction, only used by threaded programs.
+    Atomics,
+}
+
+/// Instructions of one kind in one function.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeterminismViolation {
+    /// The function's internal name.
+    pub function: String,
+    /// The kind of instruction.
+    pub kind: ViolationKind,
+    /// The number of instructions of this kind in the function.
+    pub instructions: u32,
+}
+
+/// The result of a determinism audit,
+/// returned in [`RunReports::determinism`](crate::RunReports::determinism).
+#[derive(Clone, Debug, Default)]
+pub struct DeterminismReport {
+    /// The violations in the optimized module,
+    /// grouped by function in module order.
+    pub violations: Vec<DeterminismViolation>,
+}
+
+impl DeterminismConfig {
+    /// Create a configuration that reports all violations.
+    pub fn new() -> DeterminismConfig {
+        DeterminismConfig::default()
+    }
+
+    /// Don't report scalar floating-point instructions.
+    ///
+    /// Instructions exposing NaN bits are still reported,
+    /// unless [`DeterminismConfig::canonicalize_nans`] is set.
+    pub fn allow_floats(&mut self, value: bool) -> &mut Self {
+        self.allow_floats = value;
+        self
+    }
+
+    /// Run [`Pass::DeNan`] on the optimized module before auditing it.
+    ///
+    /// This replaces NaN results with zero,
+    /// so [`ViolationKind::NanObservable`] is not reported.
+    /// Floating-point instructions remain,
+    /// so this is usually combined with [`DeterminismConfig::allow_floats`].
+    pub fn canonicalize_nans(&mut self, value: bool) -> &mut Self {
+        self.canonicalize_nans = value;
+        self
+    }
+
+    /// Fail with [`OptimizationError::Nondeterministic`](crate::OptimizationError::Nondeterministic)
+    /// instead of writing a module with violations.
+    pub fn reject(&mut self, value: bool) -> &mut Self {
+        self.reject = value;
+        self
+    }
+
+    pub(crate) fn rejects(&self) -> bool {
+        self.reject
+    }
+
+    pub(crate) fn canonicalizes_nans(&self) -> bool {
+        self.canonicalize_nans
+    }
+
+    /// Canonicalize NaNs if configured, then audit the module.
+    pub(crate) fn apply(&self, m: &mut Module, passopts: PassOptions) -> DeterminismReport {
+        if self.canonicalize_nans {
+            let mut pass_runner = PassRunner::new_with_options(m, passopts);
+            pass_runner.add(Pass::DeNan.name());
+            pass_runner.run();
+        }
+
+        self.audit(m)
+    }
+
+    fn audit(&self, m: &Module) -> DeterminismReport {
+        let float_uses = m.float_uses();
+        let feature_uses = m.feature_uses();
+
+        let count_feature = |function: &str, feature: Feature| {
+            let bit = feature.to_base() as u32;
+            feature_uses
+                .iter()
+                .find(|u| u.function == function && u.feature == bit)
+                .map(|u| u.count)
+                .unwrap_or(0)
+        };
+
+        let mut violations = vec![];
+
+        for function in m.function_names() {
+            let float_use = float_uses.iter().find(|u| u.function == function);
+            let floats = float_use.map(|u| u.floats).unwrap_or(0);
+            let nan_observable = float_use.map(|u| u.nan_observable).unwrap_or(0);
+            let simd_nan = float_use.map(|u| u.simd_nan).unwrap_or(0);
+
+            let counts = [
+                (ViolationKind::Float, floats, !self.allow_floats),
+                (
+                    ViolationKind::NanObservable,
+                    nan_observable,
+                    !self.canonicalize_nans,
+                ),
+                (ViolationKind::SimdNan, simd_nan, true),
+                (
+                    ViolationKind::RelaxedSimd,
+                    count_feature(&function, Feature::RelaxedSimd),
+                    true,
+                ),
+                (
+                    ViolationKind::Atomics,
+                    count_feature(&function, Feature::Atomics),
+                    true,
+                ),
+            ];
+
+            for &(kind, instructions, reported) in counts.iter() {
+                if reported && instructions > 0 {
+                    violations.push(DeterminismViolation {
+                        function: function.clone(),
+                        kind,
+                        instructions,
+                    });
+                }
+            }
+        }
+
+        DeterminismReport { violations }
+    }
+}
+
+impl DeterminismReport {
+    /// Returns `true` if there are no violations.
+    pub fn is_deterministic(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ViolationKind::Float => "floating-point instructions",
+            ViolationKind::NanObservable => "NaN-observable instructions",
+            ViolationKind::SimdNan => "NaN-producing SIMD instructions",
+            ViolationKind::RelaxedSimd => "relaxed SIMD instructions",
+            ViolationKind::Atomics => "atomic instructions",
+        };
+        f.write_str(s)
+    }
+}
+
+impl fmt::Display for DeterminismViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} in {}",
+            self.instructions, self.kind, self.function
+        )
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use strum_macros::EnumString;
 
 /// Optional wasm features.
@@ -11,6 +12,7 @@ use strum_macros::EnumString;
 /// used where.
 ///
 /// [rm]: https://webassembly.org/roadmap/
+// Keep these in sync with `wasm::FeatureSet::Feature`.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, EnumString)]
 pub enum Feature {
     /// None.
@@ -26,6 +28,9 @@ pub enum Feature {
     /// [Specification](https://github.com/WebAssembly/mutable-global/blob/master/proposals/mutable-global/Overview.md).
     #[strum(serialize = "mutable-globals")]
     MutableGlobals,
+    /// Non-trapping float-to-int conversions.
+    ///
+    /// [Specification](https://github.com/WebAssembly/nontrapping-float-to-int-conversions/blob/main/proposals/nontrapping-float-to-int-conversion/Overview.md).
     #[strum(serialize = "nontrapping-float-to-int")]
     TruncSat,
     /// Fixed-width SIMD.
@@ -60,9 +65,12 @@ pub enum Feature {
     ReferenceTypes,
     /// Multi-value.
     ///
-    /// [Specification](https://github.com/WebAssembly/spec/blob/master/proposals/multi-value/Overview.md)
+    /// [Specification](https://github.com/WebAssembly/spec/blob/master/proposals/multi-value/Overview.md).
     #[strum(serialize = "multivalue")]
     Multivalue,
+    /// Garbage collection.
+    ///
+    /// [Specification](https://github.com/WebAssembly/gc/blob/main/proposals/gc/Overview.md).
     #[strum(serialize = "gc")]
     Gc,
     /// Large memory.
@@ -77,15 +85,16 @@ pub enum Feature {
     RelaxedSimd,
     /// Extended constant expressions.
     ///
-    /// [Specification](https://github.com/WebAssembly/relaxed-simd/tree/main/proposals/relaxed-simd).
+    /// [Specification](https://github.com/WebAssembly/extended-const/blob/main/proposals/extended-const/Overview.md).
     #[strum(serialize = "extended-const")]
     ExtendedConst,
+    /// Reference-typed strings.
+    ///
+    /// [Specification](https://github.com/WebAssembly/stringref/blob/main/proposals/stringref/Overview.md).
     #[strum(serialize = "strings")]
     Strings,
-    /// Multiple memory.
-    ///
-    /// [Specification](https://github.com/WebAssembly/multi-memory/blob/master/proposals/multi-memory/Overview.md).
-    #[strum(serialize = "multi-memory")]
+    /// The `multimemory` feature.
+    #[strum(serialize = "multimemory")]
     MultiMemory,
     /// The original WebAssembly specification.
     ///
@@ -94,10 +103,146 @@ pub enum Feature {
     Mvp,
     /// The default feature set.
     ///
-    /// Includes [`Feature::SignExt`] and [`Feature::MutableGlobals`].
+    /// Includes [`Feature::MutableGlobals`] and [`Feature::SignExt`].
     #[strum(disabled)]
     Default,
     /// All features.
     #[strum(disabled)]
     All,
 }
+
+impl Feature {
+    pub(crate) fn to_base(self) -> crate::base::Feature {
+        match self {
+            Feature::None => crate::base::Feature::None,
+            Feature::Atomics => crate::base::Feature::Atomics,
+            Feature::MutableGlobals => crate::base::Feature::MutableGlobals,
+            Feature::TruncSat => crate::base::Feature::TruncSat,
+            Feature::Simd => crate::base::Feature::Simd,
+            Feature::BulkMemory => crate::base::Feature::BulkMemory,
+            Feature::SignExt => crate::base::Feature::SignExt,
+            Feature::ExceptionHandling => crate::base::Feature::ExceptionHandling,
+            Feature::TailCall => crate::base::Feature::TailCall,
+            Feature::ReferenceTypes => crate::base::Feature::ReferenceTypes,
+            Feature::Multivalue => crate::base::Feature::Multivalue,
+            Feature::Gc => crate::base::Feature::Gc,
+            Feature::Memory64 => crate::base::Feature::Memory64,
+            Feature::RelaxedSimd => crate::base::Feature::RelaxedSimd,
+            Feature::ExtendedConst => crate::base::Feature::ExtendedConst,
+            Feature::Strings => crate::base::Feature::Strings,
+            Feature::MultiMemory => crate::base::Feature::MultiMemory,
+            Feature::Mvp => crate::base::Feature::None,
+            Feature::Default => crate::base::Feature::Default,
+            Feature::All => crate::base::Feature::All,
+        }
+    }
+
+    /// Returns the name of the feature.
+    ///
+    /// For real features this is the name used on the `wasm-opt` command line,
+    /// as in `--enable-<name>`, and is parsed by `Feature::from_str`.
+    /// The names of [`Feature::None`], [`Feature::Mvp`],
+    /// [`Feature::Default`] and [`Feature::All`] are not parsed.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::None => "none",
+            Feature::Atomics => "threads",
+            Feature::MutableGlobals => "mutable-globals",
+            Feature::TruncSat => "nontrapping-float-to-int",
+            Feature::Simd => "simd",
+            Feature::BulkMemory => "bulk-memory",
+            Feature::SignExt => "sign-ext",
+            Feature::ExceptionHandling => "exception-handling",
+            Feature::TailCall => "tail-call",
+            Feature::ReferenceTypes => "reference-types",
+            Feature::Multivalue => "multivalue",
+            Feature::Gc => "gc",
+            Feature::Memory64 => "memory64",
+            Feature::RelaxedSimd => "relaxed-simd",
+            Feature::ExtendedConst => "extended-const",
+            Feature::Strings => "strings",
+            Feature::MultiMemory => "multimemory",
+            Feature::Mvp => "mvp",
+            Feature::Default => "default",
+            Feature::All => "all",
+        }
+    }
+}
+
+/// The real features, in the order of their bits in Binaryen's `FeatureSet`.
+const REAL_FEATURES: &[Feature] = &[
+    Feature::Atomics,
+    Feature::MutableGlobals,
+    Feature::TruncSat,
+    Feature::Simd,
+    Feature::BulkMemory,
+    Feature::SignExt,
+    Feature::ExceptionHandling,
+    Feature::TailCall,
+    Feature::ReferenceTypes,
+    Feature::Multivalue,
+    Feature::Gc,
+    Feature::Memory64,
+    Feature::RelaxedSimd,
+    Feature::ExtendedConst,
+    Feature::Strings,
+    Feature::MultiMemory,
+];
+
+impl Feature {
+    /// The features that can be enabled and disabled individually,
+    /// excluding [`Feature::None`], [`Feature::Mvp`],
+    /// [`Feature::Default`] and [`Feature::All`].
+    pub fn all_real() -> &'static [Feature] {
+        REAL_FEATURES
+    }
+
+    /// The features that Binaryen's validator rejects
+    /// when enabled without this one.
+    pub(crate) fn required_by(&self) -> &'static [Feature] {
+        match self {
+            Feature::ReferenceTypes => &[Feature::Gc],
+            _ => &[],
+        }
+    }
+}
+
+/// Displays the name of a feature, as returned by [`Feature::name`].
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_feature_names_round_trip() {
+        for feature in Feature::all_real() {
+            let name = feature.to_string();
+            assert_eq!(Feature::from_str(&name).unwrap(), *feature);
+        }
+
+        assert_eq!(Feature::Mvp.to_string(), "mvp");
+        assert!(Feature::from_str("mvp").is_err());
+        assert!(!Feature::all_real().contains(&Feature::All));
+    }
+
+    #[test]
+    fn test_real_features_match_base() {
+        let real: Vec<u32> = Feature::all_real()
+            .iter()
+            .map(|feature| feature.to_base() as u32)
+            .collect();
+        let base: Vec<u32> = crate::base::Feature::iter()
+            .map(|feature| feature as u32)
+            .filter(|bits| bits.is_power_of_two())
+            .collect();
+
+        assert_eq!(real, base);
+    }
+}
@@ -10,8 +10,9 @@
 //! of Rust.
 
 use crate::api::{Feature, FileType, OptimizationOptions, OptimizeLevel, Pass, ShrinkLevel};
-use crate::profiles::Profile;
+use crate::profiles::{Profile, ProfileRegistry};
 use crate::run::OptimizationError;
+use crate::targets::Target;
 use std::ffi::{OsStr, OsString};
 use std::iter::Iterator;
 use std::num::ParseIntError;
@@ -50,13 +51,35 @@ pub use crate::fake_command::Command;
 /// Only the arguments to `command` are interpreted;
 /// environment variables and other settings are ignored.
 ///
+/// In addition to the `wasm-opt` arguments, this accepts
+/// `--target <name>`, which replaces the feature settings with those of a
+/// [`Target`] at that point in the argument list.
+///
 /// # Errors
 ///
 /// - Returns [`Error::Unsupported`] if any argument is not understood.
 /// - Returns [`Error::OutputFileRequired`] if the `-o` argument and subsequent path
 ///   are not provided.
+/// - Returns [`Error::UnknownTarget`] if a target name is not known.
 pub fn run_from_command_args(command: Command) -> Result<(), Error> {
-    let parsed = parse_command_args(command)?;
+    run_from_command_args_with_profiles(command, &ProfileRegistry::new())
+}
+
+/// Like [`run_from_command_args`], but with custom [`Profile`]s.
+///
+/// In addition to the `wasm-opt` arguments, this accepts
+/// `--profile <name>`, which applies the named profile from `profiles`
+/// at that point in the argument list, as the `-O` arguments do.
+///
+/// # Errors
+///
+/// - Returns [`Error::UnknownProfile`] if a profile is not registered.
+/// - Otherwise as [`run_from_command_args`].
+pub fn run_from_command_args_with_profiles(
+    command: Command,
+    profiles: &ProfileRegistry,
+) -> Result<(), Error> {
+    let parsed = parse_command_args(command, profiles)?;
 
     parsed.opts.run_with_sourcemaps(
         parsed.input_file,
@@ -69,7 +92,119 @@ pub fn run_from_command_args(command: Command) -> Result<(), Error> {
     Ok(())
 }
 
-/// An error resulting from [`run_from_command_args`].
+/// The environment variable read by [`OptimizationOptions::apply_env_overrides`].
+pub const FLAGS_ENV_VAR: &str = "WASM_OPT_RS_FLAGS";
+
+/// Overrides from command-line flags.
+impl OptimizationOptions {
+    /// Apply `wasm-opt` command-line flags from the `WASM_OPT_RS_FLAGS`
+    /// environment variable on top of these options.
+    ///
+    /// This is opt-in, for tools that embed `wasm-opt` via this crate
+    /// and want to let their users tweak optimization settings
+    /// without code changes, e.g.
+    ///
+    /// ```text
+    /// WASM_OPT_RS_FLAGS="-Oz --enable-bulk-memory --strip-debug" cargo run
+    /// ```
+    ///
+    /// The flags are interpreted as by [`OptimizationOptions::apply_flags`].
+    /// If the variable is not set the options are unchanged.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::NeedUnicode`] if the variable is not unicode.
+    /// - Returns [`Error::Unsupported`] if any flag is not understood,
+    ///   or if the flags name input or output files.
+    ///
+    /// On error the options are unchanged.
+    pub fn apply_env_overrides(&mut self) -> Result<&mut Self, Error> {
+        self.apply_env_overrides_with_profiles(&ProfileRegistry::new())
+    }
+
+    /// Like [`OptimizationOptions::apply_env_overrides`], but with custom [`Profile`]s,
+    /// which the flags can apply with `--profile <name>`.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::UnknownProfile`] if a profile is not registered.
+    /// - Otherwise as [`OptimizationOptions::apply_env_overrides`].
+    pub fn apply_env_overrides_with_profiles(
+        &mut self,
+        profiles: &ProfileRegistry,
+    ) -> Result<&mut Self, Error> {
+        if let Some(flags) = std::env::var_os(FLAGS_ENV_VAR) {
+            let flags = flags
+                .into_string()
+                .map_err(|arg| Error::NeedUnicode { arg })?;
+            self.apply_flags_with_profiles(&flags, profiles)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Apply whitespace-separated `wasm-opt` command-line flags on top of these options.
+    ///
+    /// Flags are parsed the same way as by [`run_from_command_args`],
+    /// and later flags take precedence over earlier settings,
+    /// so e.g. `-O3` replaces the optimize and shrink levels,
+    /// pass flags append to [`Passes::more_passes`](crate::Passes::more_passes),
+    /// and `--enable-*` flags add to the enabled features.
+    ///
+    /// Quoting is not supported.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] if any flag is not understood,
+    /// or if the flags name input or output files.
+    ///
+    /// On error the options are unchanged.
+    pub fn apply_flags(&mut self, flags: &str) -> Result<&mut Self, Error> {
+        self.apply_flags_with_profiles(flags, &ProfileRegistry::new())
+    }
+
+    /// Like [`OptimizationOptions::apply_flags`], but with custom [`Profile`]s,
+    /// which the flags can apply with `--profile <name>`.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::UnknownProfile`] if a profile is not registered.
+    /// - Otherwise as [`OptimizationOptions::apply_flags`].
+    pub fn apply_flags_with_profiles(
+        &mut self,
+        flags: &str,
+        profiles: &ProfileRegistry,
+    ) -> Result<&mut Self, Error> {
+        let args: Vec<&OsStr> = flags.split_whitespace().map(OsStr::new).collect();
+
+        let mut opts = self.clone();
+        let paths = parse_args_into(&mut opts, args.into_iter(), profiles)?;
+
+        // Files can only be specified by the caller.
+        let mut unsupported = paths.unsupported;
+        unsupported.extend(
+            paths
+                .input_file
+                .into_iter()
+                .chain(paths.input_sourcemap)
+                .chain(paths.output_file)
+                .chain(paths.output_sourcemap)
+                .map(OsString::from),
+        );
+        unsupported.extend(paths.sourcemap_url.map(OsString::from));
+
+        if !unsupported.is_empty() {
+            return Err(Error::Unsupported { args: unsupported });
+        }
+
+        *self = opts;
+
+        Ok(self)
+    }
+}
+
+/// An error resulting from [`run_from_command_args`]
+/// or [`OptimizationOptions::apply_env_overrides`].
 #[derive(Error, Debug)]
 pub enum Error {
     /// No input file specified.
@@ -91,6 +226,12 @@ pub enum Error {
         #[source]
         source: ParseIntError,
     },
+    /// A profile named by `--profile` is not registered.
+    #[error("Unknown optimization profile: {name}")]
+    UnknownProfile { name: String },
+    /// A target named by `--target` is not known.
+    #[error("Unknown target: {name}")]
+    UnknownTarget { name: String },
     /// Unsupported or unrecognized command-line option.
     #[error("Unsupported `wasm-opt` command-line arguments: {args:?}")]
     Unsupported { args: Vec<OsString> },
@@ -112,12 +253,62 @@ struct ParsedCliArgs {
     sourcemap_url: Option<String>,
 }
 
-#[rustfmt::skip]
-fn parse_command_args(command: Command) -> Result<ParsedCliArgs, Error> {
+/// Arguments that are not optimization options.
+#[derive(Default)]
+struct ParsedPaths {
+    input_file: Option<PathBuf>,
+    input_sourcemap: Option<PathBuf>,
+    output_file: Option<PathBuf>,
+    output_sourcemap: Option<PathBuf>,
+    sourcemap_url: Option<String>,
+    unsupported: Vec<OsString>,
+}
+
+fn parse_command_args(
+    command: Command,
+    profiles: &ProfileRegistry,
+) -> Result<ParsedCliArgs, Error> {
     let mut opts = OptimizationOptions::new_opt_level_0();
 
-    let mut args = command.get_args();
+    let paths = parse_args_into(&mut opts, command.get_args(), profiles)?;
 
+    let input_file = if let Some(input_file) = paths.input_file {
+        input_file
+    } else {
+        return Err(Error::InputFileRequired);
+    };
+    let output_file = if let Some(output_file) = paths.output_file {
+        output_file
+    } else {
+        return Err(Error::OutputFileRequired);
+    };
+
+    if paths.unsupported.len() > 0 {
+        return Err(Error::Unsupported {
+            args: paths.unsupported,
+        });
+    }
+
+    Ok(ParsedCliArgs {
+        opts,
+        input_file,
+        input_sourcemap: paths.input_sourcemap,
+        output_file,
+        output_sourcemap: paths.output_sourcemap,
+        sourcemap_url: paths.sourcemap_url,
+    })
+}
+
+/// Apply command-line arguments on top of an existing `OptimizationOptions`.
+///
+/// File arguments are collected into the returned `ParsedPaths`,
+/// along with any unsupported arguments.
+#[rustfmt::skip]
+fn parse_args_into<'item>(
+    opts: &mut OptimizationOptions,
+    mut args: impl Iterator<Item = &'item OsStr>,
+    profiles: &ProfileRegistry,
+) -> Result<ParsedPaths, Error> {
     let mut input_file: Option<PathBuf> = None;
     let mut input_sourcemap: Option<PathBuf> = None;
     let mut output_file: Option<PathBuf> = None;
@@ -161,28 +352,28 @@ fn parse_command_args(command: Command) -> Result<ParsedCliArgs, Error> {
             /* from optimization-options.h */
 
             "-O" => {
-                Profile::optimize_for_size().apply_to_opts(&mut opts);
+                Profile::optimize_for_size().apply_to_opts(opts);
             }
             "-O0" => {
-                Profile::opt_level_0().apply_to_opts(&mut opts);
+                Profile::opt_level_0().apply_to_opts(opts);
             }
             "-O1" => {
-                Profile::opt_level_1().apply_to_opts(&mut opts);
+                Profile::opt_level_1().apply_to_opts(opts);
             }
             "-O2" => {
-                Profile::opt_level_2().apply_to_opts(&mut opts);
+                Profile::opt_level_2().apply_to_opts(opts);
             }
             "-O3" => {
-                Profile::opt_level_3().apply_to_opts(&mut opts);
+                Profile::opt_level_3().apply_to_opts(opts);
             }
             "-O4" => {
-                Profile::opt_level_4().apply_to_opts(&mut opts);
+                Profile::opt_level_4().apply_to_opts(opts);
             }
             "-Os" => {
-                Profile::optimize_for_size().apply_to_opts(&mut opts);
+                Profile::optimize_for_size().apply_to_opts(opts);
             }
             "-Oz" => {
-                Profile::optimize_for_size_aggressively().apply_to_opts(&mut opts);
+                Profile::optimize_for_size_aggressively().apply_to_opts(opts);
             }
             "--optimize-level" | "-ol" => {
                 match parse_unicode(&mut args)?.as_str() {
@@ -224,6 +415,9 @@ fn parse_command_args(command: Command) -> Result<ParsedCliArgs, Error> {
             "--partial-inlining-ifs" | "-pii" => {
                 opts.partial_inlining_ifs(parse_u32(&mut args)?);
             }
+            "--ignore-implicit-traps" | "-iit" => {
+                opts.ignore_implicit_traps(true);
+            }
             "--traps-never-happen" | "-tnh" => {
                 opts.traps_never_happen(true);
             }
@@ -236,6 +430,37 @@ fn parse_command_args(command: Command) -> Result<ParsedCliArgs, Error> {
             "--zero-filled-memory" | "-uim" => {
                 opts.zero_filled_memory(true);
             }
+            "--closed-world" | "-cw" => {
+                opts.closed_world(true);
+            }
+            "--skip-pass" | "-sp" => {
+                let name = parse_unicode(&mut args)?;
+                if let Ok(pass) = Pass::from_str(&name) {
+                    opts.skip_pass(pass);
+                } else {
+                    unsupported.push(OsString::from(arg));
+                    unsupported.push(OsString::from(name));
+                }
+            }
+
+            /* from wasm-opt-rs */
+
+            "--profile" => {
+                let name = parse_unicode(&mut args)?;
+                if let Some(profile) = profiles.get(&name) {
+                    profile.clone().apply_to_opts(opts);
+                } else {
+                    return Err(Error::UnknownProfile { name });
+                }
+            }
+            "--target" => {
+                let name = parse_unicode(&mut args)?;
+                if let Ok(target) = Target::from_str(&name) {
+                    opts.target(target);
+                } else {
+                    return Err(Error::UnknownTarget { name });
+                }
+            }
 
             /* from tool-options.h */
 
@@ -261,6 +486,13 @@ fn parse_command_args(command: Command) -> Result<ParsedCliArgs, Error> {
                 }
             }
 
+            /* from pass.cpp */
+
+            "--emit-target-features" => {
+                // A pass in `wasm-opt`, but it only makes sense before writing.
+                opts.emit_target_features(true);
+            }
+
             /* fallthrough */
 
             _ => {
@@ -302,30 +534,13 @@ fn parse_command_args(command: Command) -> Result<ParsedCliArgs, Error> {
         }
     }
 
-    let input_file = if let Some(input_file) = input_file {
-        input_file
-    } else {
-        return Err(Error::InputFileRequired);
-    };
-    let output_file = if let Some(output_file) = output_file {
-        output_file
-    } else {
-        return Err(Error::OutputFileRequired);
-    };
-
-    if unsupported.len() > 0 {
-        return Err(Error::Unsupported {
-            args: unsupported,
-        });
-    }
-
-    Ok(ParsedCliArgs {
-        opts,
+    Ok(ParsedPaths {
         input_file,
         input_sourcemap,
         output_file,
         output_sourcemap,
         sourcemap_url,
+        unsupported,
     })
 }
 
@@ -382,3 +597,155 @@ fn parse_u32<'item>(args: &mut impl Iterator<Item = &'item OsStr>) -> Result<u32
     })?;
     Ok(number)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply_flags() {
+        let mut opts = OptimizationOptions::new_optimize_for_size();
+        opts.add_pass(Pass::Dce);
+
+        opts.apply_flags("-O3  --enable-simd --strip-debug -g")
+            .unwrap();
+
+        assert_eq!(opts.passopts.optimize_level, OptimizeLevel::Level3);
+        assert_eq!(opts.passopts.shrink_level, ShrinkLevel::Level0);
+        assert!(opts.passopts.debug_info);
+        assert!(opts.features.enabled.contains(&Feature::Simd));
+
+        let passes: Vec<_> = opts.passes.more_passes.iter().map(Pass::name).collect();
+        assert_eq!(passes, vec!["dce", "strip-debug"]);
+    }
+
+    #[test]
+    fn test_skip_pass_arg() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+
+        opts.apply_flags("--skip-pass dce -sp vacuum").unwrap();
+
+        assert!(opts.passopts.passes_to_skip.contains(&Pass::Dce));
+        assert!(opts.passopts.passes_to_skip.contains(&Pass::Vacuum));
+
+        let res = opts.apply_flags("--skip-pass not-a-pass");
+        if let Err(Error::Unsupported { args }) = res {
+            assert_eq!(args, vec!["--skip-pass", "not-a-pass"]);
+        } else {
+            panic!("expected unsupported args");
+        }
+    }
+
+    #[test]
+    fn test_emit_target_features_arg() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+
+        opts.apply_flags("--emit-target-features").unwrap();
+
+        assert!(opts.writer.emit_target_features);
+        assert!(opts.passes.more_passes.is_empty());
+    }
+
+    #[test]
+    fn test_apply_flags_rejects_files() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+
+        let res = opts.apply_flags("-O3 input.wasm");
+        assert!(matches!(res, Err(Error::Unsupported { .. })));
+
+        let res = opts.apply_flags("-o output.wasm");
+        assert!(matches!(res, Err(Error::Unsupported { .. })));
+
+        // Errors leave the options unchanged.
+        assert_eq!(opts.passopts.optimize_level, OptimizeLevel::Level0);
+    }
+
+    #[test]
+    fn test_apply_flags_rejects_unknown() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+
+        let res = opts.apply_flags("--not-a-flag");
+        assert!(matches!(res, Err(Error::Unsupported { .. })));
+    }
+
+    #[test]
+    fn test_apply_flags_with_profiles() {
+        let mut profile = Profile::optimize_for_size_aggressively();
+        profile.passes.push(Pass::StripDebug);
+
+        let mut profiles = ProfileRegistry::new();
+        profiles.register("release-small", profile);
+
+        let mut opts = OptimizationOptions::new_opt_level_0();
+        opts.apply_flags_with_profiles("--profile release-small -g", &profiles)
+            .unwrap();
+
+        assert_eq!(opts.passopts.shrink_level, ShrinkLevel::Level2);
+        assert!(opts.passopts.debug_info);
+        assert!(matches!(opts.passes.more_passes[..], [Pass::StripDebug]));
+
+        let res = opts.apply_flags("--profile release-small");
+        assert!(matches!(res, Err(Error::UnknownProfile { .. })));
+    }
+
+    #[test]
+    fn test_profile_arg() {
+        let mut profile = Profile::optimize_for_size_aggressively();
+        profile.passes.push(Pass::StripDebug);
+
+        let mut profiles = ProfileRegistry::new();
+        profiles.register("release-small", profile);
+
+        let args = [
+            "--profile",
+            "release-small",
+            "-g",
+            "-o",
+            "out.wasm",
+            "in.wasm",
+        ];
+        let mut command = Command::new("wasm-opt");
+        command.args(args.iter());
+        let parsed = parse_command_args(command, &profiles).unwrap();
+
+        assert_eq!(parsed.opts.passopts.shrink_level, ShrinkLevel::Level2);
+        assert!(parsed.opts.passopts.debug_info);
+        assert!(matches!(
+            parsed.opts.passes.more_passes[..],
+            [Pass::StripDebug]
+        ));
+
+        let args = ["--profile", "release-big", "-o", "out.wasm", "in.wasm"];
+        let mut command = Command::new("wasm-opt");
+        command.args(args.iter());
+        let res = parse_command_args(command, &profiles);
+        assert!(matches!(res, Err(Error::UnknownProfile { .. })));
+    }
+
+    #[test]
+    fn test_target_arg() {
+        let args = [
+            "--enable-gc",
+            "--target",
+            "browser",
+            "--enable-simd",
+            "-o",
+            "out.wasm",
+            "in.wasm",
+        ];
+        let mut command = Command::new("wasm-opt");
+        command.args(args.iter());
+        let parsed = parse_command_args(command, &ProfileRegistry::new()).unwrap();
+
+        let enabled = &parsed.opts.features.enabled;
+        assert!(!enabled.contains(&Feature::Gc));
+        assert!(enabled.contains(&Feature::BulkMemory));
+        assert!(enabled.contains(&Feature::Simd));
+
+        let args = ["--target", "toaster", "-o", "out.wasm", "in.wasm"];
+        let mut command = Command::new("wasm-opt");
+        command.args(args.iter());
+        let res = parse_command_args(command, &ProfileRegistry::new());
+        assert!(matches!(res, Err(Error::UnknownTarget { .. })));
+    }
+}
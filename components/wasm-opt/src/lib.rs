@@ -118,6 +118,17 @@
 //! compatible with `std`s `Command`. This allows client code to use mostly the
 //! same code path for executing the `wasm-opt` CLI, and the crate-based API.
 //!
+//! Tools that embed the optimizer can also let their users adjust its settings
+//! with command-line flags in the `WASM_OPT_RS_FLAGS` environment variable,
+//! by calling [`OptimizationOptions::apply_env_overrides`].
+//!
+//!
+//! ## Declaring settings in `Cargo.toml`
+//!
+//! The [`config`] module loads an `OptimizationOptions` from the
+//! `[package.metadata.wasm-opt]` table of a cargo manifest,
+//! with per-cargo-profile overrides.
+//!
 //!
 //! ## Cargo features
 //!
@@ -133,11 +144,62 @@
 pub use api::*;
 
 // Returned by the `run` method.
-pub use run::OptimizationError;
+pub use run::{OptimizationError, RunReports};
+
+// Returned by the `check` method.
+pub use check::{OptionsCheck, OptionsIssue};
+
+// Returned by `Pass::custom`.
+pub use passes::UnknownPassError;
+
+// Returned when parsing a `Pipeline`.
+pub use pipeline::PipelineParseError;
+
+// Returned by the `detect_features` and `audit_features` methods.
+pub use detect::{DetectedFeatures, FeatureAudit, FeatureUsage, FunctionUsage};
+
+// Returned when `lower_unsupported_features` can't lower a feature.
+pub use lower::UnsupportedFeature;
+
+// Returned by the `planned_passes` method.
+pub use plan::PlannedPass;
 
 // Easy integration with tools that already use `wasm-opt` via CLI.
 pub mod integration;
 
+// Settings declared in `Cargo.toml` package metadata.
+pub mod config;
+
+// Definitions of -O1, -O2, etc, and user-defined presets.
+pub mod profiles;
+
+// Feature sets for common runtimes.
+pub mod targets;
+
+// Typed arguments for passes that read pass arguments.
+pub mod pass_args;
+
+// Configuration of the Asyncify pass.
+pub mod asyncify;
+
+// Auditing the optimized module for non-deterministic instructions.
+pub mod determinism;
+
+// Verifying required exports and allowed imports.
+pub mod contract;
+
+// Renaming imports and exports.
+pub mod rename;
+
+// Replacing imported functions with stubs.
+pub mod stubs;
+
+// Dead code elimination with an external reachability graph.
+pub mod metadce;
+
+// Merging modules by resolving imports against exports.
+pub mod merge;
+
 // The "base" API.
 //
 // This API hides the `cxx` types,
@@ -157,12 +219,24 @@ mod builder;
 // The list of optimization passes.
 mod passes;
 
-// Definitions of -O1, -O2, etc.
-mod profiles;
-
 // The list of wasm features.
 mod features;
 
+// The `check` method that validates `OptimizationOptions`.
+mod check;
+
+// Building and parsing `Pipeline`s.
+mod pipeline;
+
+// The `planned_passes` method.
+mod plan;
+
+// The `detect_features` and `audit_features` methods.
+mod detect;
+
+// Lowering features for `lower_unsupported_features`.
+mod lower;
+
 // The `run` method that re-implements the logic from `wasm-opt.cpp`
 // on top of `OptimizationOptions`.
 mod run;
@@ -0,0 +1,188 @@
+//! Lowering features the configured feature set doesn't support.
+
+use crate::api::*;
+use crate::base::{Module, PassRunner};
+use crate::detect::{DetectedFeatures, FeatureAudit};
+use crate::run::OptimizationError;
+
+/// A feature that a module uses but the configured feature set lacks,
+/// and that could not be lowered away.
+///
+/// Returned in [`OptimizationError::UnsupportedFeatures`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnsupportedFeature {
+    /// The unsupported feature.
+    pub feature: Feature,
+    /// The defined functions that use the feature.
+    ///
+    /// This may be empty if the feature is only used outside of function bodies,
+    /// for example by the type of a global or the index type of a memory.
+    pub functions: Vec<String>,
+}
+
+impl Feature {
+    /// The pass that removes uses of this feature, if there is one.
+    ///
+    /// These are the passes scheduled by
+    /// [`OptimizationOptions::lower_unsupported_features`],
+    /// which preserve the module's behavior.
+    ///
+    /// Exception handling is lowered with [`Pass::StripEh`] only if
+    /// [`LoweringOptions::strip_exceptions`] is set,
+    /// since that makes `throw` trap.
+    /// Lowering that doesn't correspond to a feature,
+    /// like [`Pass::I64ToI32Lowering`] and [`Pass::AlignmentLowering`],
+    /// is requested with [`LoweringOptions`].
+    pub fn lowering_pass(&self) -> Option<Pass> {
+        match self {
+            Feature::SignExt => Some(Pass::SignextLowering),
+            Feature::Memory64 => Some(Pass::Memory64Lowering),
+            Feature::MultiMemory => Some(Pass::MultiMemoryLowering),
+            _ => None,
+        }
+    }
+}
+
+/// Feature lowering.
+impl OptimizationOptions {
+    /// Run the lowering passes for the features the module uses
+    /// that are not in `target`, then restrict the module to `target`.
+    ///
+    /// The module must have been read with all features enabled.
+    pub(crate) fn lower_features(
+        &self,
+        m: &mut Module,
+        target: u32,
+    ) -> Result<(), OptimizationError> {
+        let detected = DetectedFeatures::detect(m).ok_or(OptimizationError::ValidateWasmInput)?;
+
+        let lowering_passes: Vec<Pass> = detected
+            .used
+            .iter()
+            .filter(|feature| target & feature.to_base() as u32 == 0)
+            .filter_map(|feature| self.feature_lowering_pass(*feature))
+            .collect();
+
+        self.run_lowering_passes(m, &lowering_passes);
+
+        let detected = DetectedFeatures::detect(m).ok_or(OptimizationError::ValidateWasmInput)?;
+        let audit = FeatureAudit::from_module(m);
+
+        let unsupported: Vec<UnsupportedFeature> = detected
+            .used
+            .iter()
+            .filter(|feature| target & feature.to_base() as u32 == 0)
+            .map(|feature| UnsupportedFeature {
+                feature: *feature,
+                functions: audit
+                    .get(*feature)
+                    .map(|usage| usage.functions.iter().map(|f| f.function.clone()).collect())
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        if !unsupported.is_empty() {
+            return Err(OptimizationError::UnsupportedFeatures { unsupported });
+        }
+
+        m.set_features(target);
+
+        Ok(())
+    }
+
+    /// Run the lowering passes requested by [`OptimizationOptions::lowering`]
+    /// that don't correspond to a feature.
+    pub(crate) fn apply_lowering_options(&self, m: &mut Module) {
+        self.run_lowering_passes(m, &self.requested_lowering_passes());
+    }
+
+    /// The lowering pass for a feature the configured feature set lacks.
+    pub(crate) fn feature_lowering_pass(&self, feature: Feature) -> Option<Pass> {
+        match feature {
+            Feature::ExceptionHandling if self.lowering.strip_exceptions => Some(Pass::StripEh),
+            _ => feature.lowering_pass(),
+        }
+    }
+
+    /// The passes for [`LoweringOptions::i64_to_i32`] and [`LoweringOptions::alignment`].
+    pub(crate) fn requested_lowering_passes(&self) -> Vec<Pass> {
+        let mut passes = vec![];
+
+        if self.lowering.i64_to_i32 {
+            // `i64-to-i32-lowering` requires flat IR,
+            // and can't lower the operations `remove-non-js-ops` replaces.
+            passes.push(Pass::RemoveNonJsOps);
+            passes.push(Pass::Flatten);
+            passes.push(Pass::I64ToI32Lowering);
+        }
+
+        if self.lowering.alignment {
+            passes.push(Pass::AlignmentLowering);
+        }
+
+        passes
+    }
+
+    fn run_lowering_passes(&self, m: &mut Module, passes: &[Pass]) {
+        if passes.is_empty() {
+            return;
+        }
+
+        let mut pass_runner = PassRunner::new_with_options(m, self.translate_pass_options());
+        for pass in passes {
+            pass_runner.add(pass.name());
+        }
+        pass_runner.run();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lowering_passes_are_for_real_features() {
+        for feature in Feature::all_real() {
+            if let Some(pass) = feature.lowering_pass() {
+                assert!(!pass.name().is_empty());
+            }
+        }
+
+        assert_eq!(Feature::Default.lowering_pass(), None);
+        assert_eq!(Feature::Simd.lowering_pass(), None);
+        assert_eq!(
+            Feature::SignExt.lowering_pass(),
+            Some(Pass::SignextLowering)
+        );
+        assert_eq!(Feature::ExceptionHandling.lowering_pass(), None);
+    }
+
+    #[test]
+    fn stripping_exceptions_is_opt_in() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+        assert_eq!(opts.feature_lowering_pass(Feature::ExceptionHandling), None);
+
+        opts.strip_exceptions(true);
+        assert_eq!(
+            opts.feature_lowering_pass(Feature::ExceptionHandling),
+            Some(Pass::StripEh)
+        );
+    }
+
+    #[test]
+    fn requested_lowering_passes() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+        assert!(opts.requested_lowering_passes().is_empty());
+
+        opts.lower_i64_to_i32(true).lower_alignment(true);
+        assert_eq!(
+            opts.requested_lowering_passes(),
+            vec![
+                Pass::RemoveNonJsOps,
+                Pass::Flatten,
+                Pass::I64ToI32Lowering,
+                Pass::AlignmentLowering
+            ]
+        );
+    }
+}
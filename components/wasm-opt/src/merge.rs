@@ -0,0 +1,312 @@
+//! Merging modules.
+//!
+//! Binaryen's `wasm-merge` tool combines modules into one,
+//! resolving the imports of each module against the exports of the others,
+//! as a host linking them would.
+//! Each module is given a name, which other modules import it by.
+//! Imported functions, globals, memories, tables and tags are resolved,
+//! and the resolved imports are removed.
+//!
+//! If the modules have start functions they are called
+//! in the order of the modules.
+//!
+//! Modules are merged by Binaryen's own `wasm-merge` logic,
+//! either in memory with [`merge`], or read from files with [`merge_files`].
+//! The merged module can be optimized with
+//! [`OptimizationOptions::run_merged`](crate::OptimizationOptions::run_merged).
+//!
+//! ```no_run
+//! use wasm_opt::OptimizationOptions;
+//! use wasm_opt::merge::merge_files;
+//!
+//! let merged = merge_files(&[("app", "app.wasm"), ("lib", "lib.wasm")])?;
+//!
+//! for import in &merged.report().resolved {
+//!     println!("resolved: {}.{}", import.module, import.base);
+//! }
+//!
+//! OptimizationOptions::new_optimize_for_size().run_merged(merged, "output.wasm")?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use crate::base::{validate_wasm, Feature as BaseFeature, ModuleMerger, ModuleReader};
+use crate::contract::ExternalKind;
+use std::collections::HashSet;
+use std::path::Path;
+use thiserror::Error;
+
+pub use crate::base::Module;
+
+/// Options for merging modules.
+#[derive(Clone, Debug, Default)]
+pub struct MergeOptions {
+    /// How to handle exports with the same name in different modules.
+    ///
+    /// Default: [`ExportConflicts::Error`].
+    pub export_conflicts: ExportConflicts,
+    /// Don't validate the input modules and the merged module.
+    ///
+    /// Default: `false`.
+    pub skip_validation: bool,
+}
+
+/// How to handle exports with the same name in different modules.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ExportConflicts {
+    /// Return [`MergeError::ExportConflict`].
+    #[default]
+    Error,
+    /// Rename later exports with a suffix, as `main`, `main_1`, `main_2`.
+    Rename,
+    /// Skip later exports.
+    ///
+    /// Useful when the first module is the program,
+    /// and the others are libraries only it uses.
+    Skip,
+}
+
+/// An error resulting from merging modules.
+#[derive(Error, Debug)]
+pub enum MergeError {
+    /// An input module failed to read.
+    #[error("Failed to read module {name}")]
+    Read {
+        name: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+    /// An input module did not validate.
+    #[error("Failed to validate module {name}")]
+    ValidateInput { name: String },
+    /// The merged module did not validate.
+    #[error("Failed to validate merged module")]
+    ValidateMerged,
+    /// Two modules have an export with the same name,
+    /// and [`MergeOptions::export_conflicts`] is [`ExportConflicts::Error`].
+    #[error("Export name conflict in module {name}: {export}")]
+    ExportConflict { name: String, export: String },
+    /// Binaryen failed to merge the modules,
+    /// as when imports and exports form a loop.
+    #[error("Failed to merge modules")]
+    Merge {
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+}
+
+/// A module merged from several modules, returned by [`merge`].
+///
+/// Optimize it with
+/// [`OptimizationOptions::run_merged`](crate::OptimizationOptions::run_merged).
+pub struct MergedModule {
+    pub(crate) module: Module,
+    report: MergeReport,
+}
+
+/// The imports resolved by merging.
+#[derive(Clone, Debug, Default)]
+pub struct MergeReport {
+    /// The imports resolved against the exports of a merged module,
+    /// in the order of the modules.
+    pub resolved: Vec<MergedImport>,
+    /// The imports the merged module still has,
+    /// which the host must provide.
+    pub unresolved: Vec<MergedImport>,
+}
+
+/// An import of a merged module.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MergedImport {
+    /// The import module.
+    pub module: String,
+    /// The import name.
+    pub base: String,
+    /// The kind of the import.
+    pub kind: ExternalKind,
+}
+
+/// Merge modules, each with the name other modules import it by.
+///
+/// This uses the default [`MergeOptions`].
+pub fn merge<N>(modules: &[(N, Module)]) -> Result<MergedModule, MergeError>
+where
+    N: AsRef<str>,
+{
+    MergeOptions::new().merge(modules)
+}
+
+/// Merge module files, each with the name other modules import it by.
+///
+/// This uses the default [`MergeOptions`].
+pub fn merge_files<N, P>(modules: &[(N, P)]) -> Result<MergedModule, MergeError>
+where
+    N: AsRef<str>,
+    P: AsRef<Path>,
+{
+    MergeOptions::new().merge_files(modules)
+}
+
+impl MergeOptions {
+    /// Create the default options.
+    pub fn new() -> MergeOptions {
+        MergeOptions::default()
+    }
+
+    /// Set how to handle exports with the same name in different modules.
+    pub fn export_conflicts(&mut self, export_conflicts: ExportConflicts) -> &mut Self {
+        self.export_conflicts = export_conflicts;
+        self
+    }
+
+    /// Don't validate the input modules and the merged module.
+    pub fn skip_validation(&mut self, skip: bool) -> &mut Self {
+        self.skip_validation = skip;
+        self
+    }
+
+    /// Merge modules, each with the name other modules import it by.
+    ///
+    /// The modules are not modified.
+    /// The merged module has all features enabled,
+    /// and its features are set when it is optimized.
+    pub fn merge<N>(&self, modules: &[(N, Module)]) -> Result<MergedModule, MergeError>
+    where
+        N: AsRef<str>,
+    {
+        let modules: Vec<(&str, &Module)> = modules
+            .iter()
+            .map(|(name, module)| (name.as_ref(), module))
+            .collect();
+
+        self.merge_modules(&modules)
+    }
+
+    /// Merge module files, each with the name other modules import it by.
+    ///
+    /// Modules are read with all features enabled.
+    pub fn merge_files<N, P>(&self, modules: &[(N, P)]) -> Result<MergedModule, MergeError>
+    where
+        N: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let mut read = Vec::new();
+        for (name, path) in modules {
+            let name = name.as_ref();
+
+            let mut m = Module::new();
+            m.set_features(BaseFeature::All as u32);
+
+            let mut reader = ModuleReader::new();
+            reader
+                .read(path.as_ref(), &mut m, None)
+                .map_err(|e| MergeError::Read {
+                    name: name.to_string(),
+                    source: Box::from(e),
+                })?;
+
+            read.push((name, m));
+        }
+
+        let modules: Vec<(&str, &Module)> = read.iter().map(|(name, m)| (*name, m)).collect();
+
+        self.merge_modules(&modules)
+    }
+
+    fn merge_modules(&self, modules: &[(&str, &Module)]) -> Result<MergedModule, MergeError> {
+        // Binaryen's `ExportMergeMode`.
+        let export_mode = match self.export_conflicts {
+            ExportConflicts::Error => 0,
+            ExportConflicts::Rename => 1,
+            ExportConflicts::Skip => 2,
+        };
+        let mut merger = ModuleMerger::new(export_mode);
+        let mut export_names = HashSet::new();
+
+        for (name, m) in modules {
+            if !self.skip_validation && !validate_wasm(&mut m.copy()) {
+                return Err(MergeError::ValidateInput {
+                    name: name.to_string(),
+                });
+            }
+
+            if self.export_conflicts == ExportConflicts::Error {
+                for export in m.exports() {
+                    if !export_names.insert(export.name.clone()) {
+                        return Err(MergeError::ExportConflict {
+                            name: name.to_string(),
+                            export: export.name,
+                        });
+                    }
+                }
+            }
+
+            merger.add_module(m, name);
+        }
+
+        let resolved = self.resolved_imports(modules);
+
+        let mut module = merger.merge().map_err(|e| MergeError::Merge {
+            source: Box::from(e),
+        })?;
+        module.set_features(BaseFeature::All as u32);
+
+        if !self.skip_validation && !validate_wasm(&mut module) {
+            return Err(MergeError::ValidateMerged);
+        }
+
+        let unresolved = module.imports();
+
+        Ok(MergedModule {
+            module,
+            report: MergeReport {
+                resolved,
+                unresolved: unresolved
+                    .into_iter()
+                    .map(MergedImport::from_base)
+                    .collect(),
+            },
+        })
+    }
+
+    /// The imports `wasm-merge` resolves against the exports of the modules.
+    ///
+    /// Skipped conflicting exports don't resolve imports.
+    fn resolved_imports(&self, modules: &[(&str, &Module)]) -> Vec<MergedImport> {
+        let mut export_names = HashSet::new();
+        let mut exports = HashSet::new();
+        for (name, m) in modules {
+            for export in m.exports() {
+                let kept = export_names.insert(export.name.clone());
+                if kept || self.export_conflicts != ExportConflicts::Skip {
+                    exports.insert((name.to_string(), export.name, export.kind));
+                }
+            }
+        }
+
+        modules
+            .iter()
+            .flat_map(|(_, m)| m.imports())
+            .filter(|import| {
+                exports.contains(&(import.module.clone(), import.base.clone(), import.kind))
+            })
+            .map(MergedImport::from_base)
+            .collect()
+    }
+}
+
+impl MergedModule {
+    /// The imports resolved by merging.
+    pub fn report(&self) -> &MergeReport {
+        &self.report
+    }
+}
+
+impl MergedImport {
+    fn from_base(import: crate::base::ImportInfo) -> MergedImport {
+        MergedImport {
+            module: import.module,
+            base: import.base,
+            kind: ExternalKind::from_base(import.kind),
+        }
+    }
+}
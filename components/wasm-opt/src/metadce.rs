@@ -0,0 +1,383 @@
+//! Dead code elimination with an external reachability graph.
+//!
+//! A module's exports are only useful if its host uses them,
+//! and its imports call back into the host, which may use more exports.
+//! A [`MetaDceGraph`] describes the host side:
+//! nodes that are roots or reached from other nodes,
+//! which may stand for an export or an import of the module.
+//! Set with [`OptimizationOptions::metadce`](crate::OptimizationOptions::metadce),
+//! exports of unreached nodes are removed before optimizing,
+//! and everything the remaining exports don't use is removed with them.
+//!
+//! This follows the model of Binaryen's `wasm-metadce` tool,
+//! which is not built by this crate.
+//!
+//! ```no_run
+//! use wasm_opt::OptimizationOptions;
+//! use wasm_opt::metadce::MetaDceGraph;
+//!
+//! let mut graph = MetaDceGraph::new();
+//! graph.node("main").root().export("main");
+//! graph.node("on_event").export("handle_event");
+//! graph.node("register").import("env", "register").reaches("on_event");
+//!
+//! let reports = OptimizationOptions::new_optimize_for_size()
+//!     .metadce(graph)
+//!     .run_with_reports("input.wasm", "output.wasm")?;
+//!
+//! for name in &reports.metadce.unwrap_or_default().unused_nodes {
+//!     println!("unused: {}", name);
+//! }
+//! # Ok::<(), wasm_opt::OptimizationError>(())
+//! ```
+
+use crate::api::Pass;
+use crate::base::{Module, PassOptions, PassRunner};
+use crate::contract::ExternalKind;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+/// The host side of a module's reachability graph.
+///
+/// Set with [`OptimizationOptions::metadce`](crate::OptimizationOptions::metadce).
+///
+/// Exports not named by a node are unreachable, and are removed.
+/// Imports not named by a node are kept if the module uses them.
+#[derive(Clone, Debug, Default)]
+pub struct MetaDceGraph {
+    nodes: Vec<GraphNode>,
+}
+
+/// A node of a [`MetaDceGraph`].
+#[derive(Clone, Debug, Default)]
+pub struct GraphNode {
+    name: String,
+    root: bool,
+    export: Option<String>,
+    import: Option<(String, String)>,
+    reaches: Vec<String>,
+}
+
+/// An error resulting from a [`MetaDceGraph`]
+/// that is inconsistent or doesn't match the module.
+#[derive(Error, Debug)]
+pub enum MetaDceError {
+    /// Two nodes have the same name.
+    #[error("Duplicate graph node: {name}")]
+    DuplicateNode { name: String },
+    /// A node reaches a node that is not in the graph.
+    #[error("Graph node {from} reaches unknown node {to}")]
+    UnknownNode { from: String, to: String },
+    /// A node names an export that is not in the module.
+    #[error("Graph node {name} names unknown export {export}")]
+    UnknownExport { name: String, export: String },
+    /// A node names an import that is not in the module.
+    #[error("Graph node {name} names unknown import {module}.{base}")]
+    UnknownImport {
+        name: String,
+        module: String,
+        base: String,
+    },
+}
+
+/// The result of dead code elimination,
+/// returned in [`RunReports::metadce`](crate::RunReports::metadce).
+#[derive(Clone, Debug, Default)]
+pub struct MetaDceReport {
+    /// The graph nodes that are not reached, in graph order.
+    ///
+    /// The host can remove the code they stand for.
+    ///
+    /// Whether a node is reached does not depend on its export:
+    /// a node whose export was removed by
+    /// [`OptimizationOptions::export_allowlist`](crate::OptimizationOptions::export_allowlist)
+    /// is listed only if the roots don't reach it, like any other node.
+    pub unused_nodes: Vec<String>,
+    /// The removed exports, in module order.
+    pub removed_exports: Vec<String>,
+    /// The removed imports, in module order.
+    pub removed_imports: Vec<RemovedImport>,
+    /// The internal names of removed functions, including imported functions,
+    /// in module order.
+    pub removed_functions: Vec<String>,
+}
+
+/// An import removed by dead code elimination.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RemovedImport {
+    /// The import module.
+    pub module: String,
+    /// The import name.
+    pub base: String,
+    /// The kind of the import.
+    pub kind: ExternalKind,
+}
+
+impl MetaDceGraph {
+    /// Create an empty graph.
+    pub fn new() -> MetaDceGraph {
+        MetaDceGraph::default()
+    }
+
+    /// Add a node to the graph.
+    pub fn node(&mut self, name: &str) -> &mut GraphNode {
+        self.nodes.push(GraphNode {
+            name: name.to_string(),
+            ..GraphNode::default()
+        });
+        self.nodes.last_mut().expect("node")
+    }
+
+    /// Remove unreached exports and everything only they use.
+    ///
+    /// Nodes may name the `pruned_exports` already removed from the module,
+    /// which are not kept even if the node is reached.
+    pub(crate) fn apply(
+        &self,
+        m: &mut Module,
+        pruned_exports: &[String],
+        passopts: impl Fn() -> PassOptions,
+    ) -> Result<MetaDceReport, MetaDceError> {
+        self.validate(m, pruned_exports)?;
+
+        let exports: Vec<String> = m.exports().into_iter().map(|e| e.name).collect();
+        let imports = m.imports();
+        let functions = m.function_names();
+
+        let mut reached = self.reach(
+            self.nodes
+                .iter()
+                .filter(|node| node.root)
+                .map(|node| node.name.as_str()),
+            HashSet::new(),
+        );
+
+        // Imports used by the remaining exports call into the host,
+        // which may reach more nodes.
+        loop {
+            let mut copy = m.copy();
+            remove_dead(
+                &mut copy,
+                &self.dead_exports(&exports, &reached),
+                passopts(),
+            );
+
+            let used_imports = copy.imports();
+            let newly_reached: Vec<&str> = self
+                .nodes
+                .iter()
+                .filter(|node| !reached.contains(node.name.as_str()))
+                .filter(|node| match &node.import {
+                    Some((module, base)) => used_imports
+                        .iter()
+                        .any(|i| i.module == *module && i.base == *base),
+                    None => false,
+                })
+                .map(|node| node.name.as_str())
+                .collect();
+
+            if newly_reached.is_empty() {
+                break;
+            }
+
+            reached = self.reach(newly_reached.into_iter(), reached);
+        }
+
+        let removed_exports = self.dead_exports(&exports, &reached);
+        remove_dead(m, &removed_exports, passopts());
+
+        let remaining_imports = m.imports();
+        let remaining_functions: HashSet<String> = m.function_names().into_iter().collect();
+
+        Ok(MetaDceReport {
+            unused_nodes: self
+                .nodes
+                .iter()
+                .filter(|node| !reached.contains(node.name.as_str()))
+                .map(|node| node.name.clone())
+                .collect(),
+            removed_exports,
+            removed_imports: imports
+                .into_iter()
+                .filter(|i| {
+                    !remaining_imports
+                        .iter()
+                        .any(|r| r.module == i.module && r.base == i.base && r.kind == i.kind)
+                })
+                .map(|i| RemovedImport {
+                    module: i.module,
+                    base: i.base,
+                    kind: ExternalKind::from_base(i.kind),
+                })
+                .collect(),
+            removed_functions: functions
+                .into_iter()
+                .filter(|f| !remaining_functions.contains(f))
+                .collect(),
+        })
+    }
+
+    fn validate(&self, m: &Module, pruned_exports: &[String]) -> Result<(), MetaDceError> {
+        let mut names = HashSet::new();
+        for node in &self.nodes {
+            if !names.insert(node.name.as_str()) {
+                return Err(MetaDceError::DuplicateNode {
+                    name: node.name.clone(),
+                });
+            }
+        }
+
+        for node in &self.nodes {
+            if let Some(to) = node.reaches.iter().find(|to| !names.contains(to.as_str())) {
+                return Err(MetaDceError::UnknownNode {
+                    from: node.name.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+
+        let exports = m.exports();
+        for node in &self.nodes {
+            if let Some(export) = &node.export {
+                if !exports.iter().any(|e| e.name == *export) && !pruned_exports.contains(export) {
+                    return Err(MetaDceError::UnknownExport {
+                        name: node.name.clone(),
+                        export: export.clone(),
+                    });
+                }
+            }
+        }
+
+        let imports = m.imports();
+        for node in &self.nodes {
+            if let Some((module, base)) = &node.import {
+                if !imports
+                    .iter()
+                    .any(|i| i.module == *module && i.base == *base)
+                {
+                    return Err(MetaDceError::UnknownImport {
+                        name: node.name.clone(),
+                        module: module.clone(),
+                        base: base.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The nodes reachable from `from`, added to `reached`.
+    fn reach<'a>(
+        &'a self,
+        from: impl Iterator<Item = &'a str>,
+        mut reached: HashSet<&'a str>,
+    ) -> HashSet<&'a str> {
+        let nodes: HashMap<&str, &GraphNode> = self
+            .nodes
+            .iter()
+            .map(|node| (node.name.as_str(), node))
+            .collect();
+
+        let mut stack: Vec<&str> = from.collect();
+        while let Some(name) = stack.pop() {
+            if reached.insert(name) {
+                stack.extend(nodes[name].reaches.iter().map(String::as_str));
+            }
+        }
+
+        reached
+    }
+
+    /// The exports not kept by a reached node.
+    fn dead_exports(&self, exports: &[String], reached: &HashSet<&str>) -> Vec<String> {
+        exports
+            .iter()
+            .filter(|export| {
+                !self.nodes.iter().any(|node| {
+                    reached.contains(node.name.as_str()) && node.export.as_ref() == Some(export)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl GraphNode {
+    /// Make the node a root, which is always reached.
+    pub fn root(&mut self) -> &mut Self {
+        self.root = true;
+        self
+    }
+
+    /// The node stands for an export, which is kept if the node is reached.
+    pub fn export(&mut self, name: &str) -> &mut Self {
+        self.export = Some(name.to_string());
+        self
+    }
+
+    /// The node stands for an import, which reaches the node
+    /// if the module still uses it.
+    ///
+    /// The import must be in the module,
+    /// under its name after [`OptimizationOptions::rename`](crate::OptimizationOptions::rename).
+    pub fn import(&mut self, module: &str, base: &str) -> &mut Self {
+        self.import = Some((module.to_string(), base.to_string()));
+        self
+    }
+
+    /// The node reaches another node.
+    pub fn reaches(&mut self, name: &str) -> &mut Self {
+        self.reaches.push(name.to_string());
+        self
+    }
+}
+
+fn remove_dead(m: &mut Module, dead_exports: &[String], passopts: PassOptions) {
+    for export in dead_exports {
+        m.remove_export(export);
+    }
+
+    let mut pass_runner = PassRunner::new_with_options(m, passopts);
+    pass_runner.add(Pass::RemoveUnusedModuleElements.name());
+    pass_runner.run();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reach() {
+        let mut graph = MetaDceGraph::new();
+        graph.node("a").root().reaches("b");
+        graph.node("b").reaches("c");
+        graph.node("c").reaches("a");
+        graph.node("d").reaches("a");
+
+        let reached = graph.reach(vec!["a"].into_iter(), HashSet::new());
+        assert_eq!(reached, vec!["a", "b", "c"].into_iter().collect());
+
+        let reached = graph.reach(vec!["d"].into_iter(), reached);
+        assert!(reached.contains("d"));
+    }
+
+    #[test]
+    fn test_dead_exports() {
+        let mut graph = MetaDceGraph::new();
+        graph.node("a").root().export("main");
+        graph.node("b").export("unused");
+
+        let reached = graph.reach(vec!["a"].into_iter(), HashSet::new());
+        let exports = vec![
+            "main".to_string(),
+            "unused".to_string(),
+            "unlisted".to_string(),
+        ];
+
+        assert_eq!(
+            graph.dead_exports(&exports, &reached),
+            vec!["unused".to_string(), "unlisted".to_string()]
+        );
+    }
+}
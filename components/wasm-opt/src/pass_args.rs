@@ -0,0 +1,395 @@
+//! Typed arguments for passes that are configured by pass arguments.
+//!
+//! Binaryen passes read their configuration from the untyped
+//! [`PassOptions::arguments`](crate::PassOptions::arguments) map,
+//! which is set on the command line with `--pass-arg key@value`.
+//! The types in this module produce the right keys and values for each pass,
+//! and reject values that Binaryen would fail on or misinterpret.
+//!
+//! Add a pass along with its arguments with
+//! [`OptimizationOptions::add_pass_with_args`]:
+//!
+//! ```
+//! use wasm_opt::OptimizationOptions;
+//! use wasm_opt::pass_args::SetGlobalsArgs;
+//!
+//! let mut opts = OptimizationOptions::new_optimize_for_size();
+//! opts.add_pass_with_args(SetGlobalsArgs {
+//!     globals: vec![("DEBUG".to_string(), 0)],
+//! })?;
+//! # Ok::<(), wasm_opt::pass_args::PassArgsError>(())
+//! ```
+//!
+//! The `instrument-locals`, `limit-segments` and `multi-memory-lowering`
+//! passes read no pass arguments in this version of Binaryen,
+//! so have no argument types.
+
+use crate::api::{OptimizationOptions, Pass};
+use thiserror::Error;
+
+/// Arguments for a pass.
+pub trait PassArgs {
+    /// The pass these arguments configure.
+    fn pass(&self) -> Pass;
+
+    /// Validate the arguments and convert them to
+    /// [`PassOptions::arguments`](crate::PassOptions::arguments) entries.
+    fn to_arguments(&self) -> Result<Vec<(String, String)>, PassArgsError>;
+}
+
+/// An error resulting from invalid [`PassArgs`].
+#[derive(Error, Clone, Debug)]
+pub enum PassArgsError {
+    /// A name can't be represented in the pass argument syntax.
+    #[error("Invalid name in `{argument}` pass argument: {name:?}")]
+    InvalidName {
+        argument: &'static str,
+        name: String,
+    },
+    /// A required list is empty.
+    #[error("The `{argument}` pass argument requires at least one name")]
+    EmptyList { argument: &'static str },
+    /// A value is out of the range Binaryen accepts.
+    #[error("Invalid value for `{argument}` pass argument: {value}")]
+    InvalidValue {
+        argument: &'static str,
+        value: String,
+    },
+    /// Two pass arguments can't be used together.
+    #[error("The `{argument}` and `{other}` pass arguments can't be used together")]
+    Conflicting {
+        argument: &'static str,
+        other: &'static str,
+    },
+}
+
+/// Arguments for [`Pass::ExtractFunction`].
+#[derive(Clone, Debug)]
+pub struct ExtractFunctionArgs {
+    /// The name of the function to keep.
+    pub function: String,
+}
+
+impl PassArgs for ExtractFunctionArgs {
+    fn pass(&self) -> Pass {
+        Pass::ExtractFunction
+    }
+
+    fn to_arguments(&self) -> Result<Vec<(String, String)>, PassArgsError> {
+        check_name("extract-function", &self.function)?;
+        Ok(vec![(
+            "extract-function".to_string(),
+            self.function.clone(),
+        )])
+    }
+}
+
+/// Arguments for [`Pass::ExtractFunctionIndex`].
+#[derive(Clone, Debug)]
+pub struct ExtractFunctionIndexArgs {
+    /// The index of the function to keep.
+    pub index: u32,
+}
+
+impl PassArgs for ExtractFunctionIndexArgs {
+    fn pass(&self) -> Pass {
+        Pass::ExtractFunctionIndex
+    }
+
+    fn to_arguments(&self) -> Result<Vec<(String, String)>, PassArgsError> {
+        Ok(vec![(
+            "extract-function-index".to_string(),
+            self.index.to_string(),
+        )])
+    }
+}
+
+/// Arguments for [`Pass::SetGlobals`].
+#[derive(Clone, Debug)]
+pub struct SetGlobalsArgs {
+    /// Names of integer globals and the values to set them to.
+    pub globals: Vec<(String, i64)>,
+}
+
+impl PassArgs for SetGlobalsArgs {
+    fn pass(&self) -> Pass {
+        Pass::SetGlobals
+    }
+
+    fn to_arguments(&self) -> Result<Vec<(String, String)>, PassArgsError> {
+        let argument = "set-globals";
+
+        if self.globals.is_empty() {
+            return Err(PassArgsError::EmptyList { argument });
+        }
+
+        let mut pairs = vec![];
+        for (name, value) in &self.globals {
+            check_list_name(argument, name)?;
+            if name.contains('=') {
+                return Err(invalid_name(argument, name));
+            }
+            pairs.push(format!("{}={}", name, value));
+        }
+
+        Ok(vec![(argument.to_string(), pairs.join(","))])
+    }
+}
+
+/// Arguments for [`Pass::Asyncify`].
+///
+/// Function names in the lists may contain `*` wildcards.
+#[derive(Clone, Debug, Default)]
+pub struct AsyncifyArgs {
+    /// Imports that may start an unwind or rewind, as `module.base` names.
+    ///
+    /// If empty, and [`AsyncifyArgs::ignore_imports`] is not set,
+    /// all imports are assumed to.
+    pub imports: Vec<String>,
+    /// Assume no imports start an unwind or rewind.
+    pub ignore_imports: bool,
+    /// Assume indirect calls never unwind or rewind.
+    pub ignore_indirect: bool,
+    /// Functions that should not be instrumented.
+    pub remove_list: Vec<String>,
+    /// Functions that should be instrumented, in addition to those found by analysis.
+    pub add_list: Vec<String>,
+    /// Only instrument these functions.
+    ///
+    /// Can't be used together with [`AsyncifyArgs::remove_list`]
+    /// or [`AsyncifyArgs::add_list`].
+    pub only_list: Vec<String>,
+    /// Add assertions that unwinding doesn't happen where it is not expected.
+    pub asserts: bool,
+    /// Print which functions are instrumented, and why.
+    pub verbose: bool,
+    /// Import the asyncify state globals, for relocatable modules.
+    pub relocatable: bool,
+    /// Keep the asyncify data in a new memory,
+    /// rather than in the module's memory.
+    pub in_secondary_memory: bool,
+    /// The size of the secondary memory in pages.
+    ///
+    /// Only used with [`AsyncifyArgs::in_secondary_memory`].
+    /// If `None`, Binaryen's default of one page is used.
+    pub secondary_memory_size: Option<u32>,
+}
+
+/// The most pages a 32-bit memory can have.
+const MAX_MEMORY_PAGES: u32 = 65536;
+
+impl PassArgs for AsyncifyArgs {
+    fn pass(&self) -> Pass {
+        Pass::Asyncify
+    }
+
+    fn to_arguments(&self) -> Result<Vec<(String, String)>, PassArgsError> {
+        let mut arguments = vec![];
+
+        for import in &self.imports {
+            if !import.contains('.') {
+                return Err(invalid_name("asyncify-imports", import));
+            }
+        }
+
+        for (argument, list) in [
+            ("asyncify-removelist", &self.remove_list),
+            ("asyncify-addlist", &self.add_list),
+        ]
+        .iter()
+        {
+            if !self.only_list.is_empty() && !list.is_empty() {
+                return Err(PassArgsError::Conflicting {
+                    argument: "asyncify-onlylist",
+                    other: argument,
+                });
+            }
+        }
+
+        let lists = [
+            ("asyncify-imports", &self.imports),
+            ("asyncify-removelist", &self.remove_list),
+            ("asyncify-addlist", &self.add_list),
+            ("asyncify-onlylist", &self.only_list),
+        ];
+        for (argument, names) in lists.iter() {
+            if names.is_empty() {
+                continue;
+            }
+            for name in names.iter() {
+                check_list_name(argument, name)?;
+            }
+            arguments.push((argument.to_string(), names.join(",")));
+        }
+
+        let flags = [
+            ("asyncify-ignore-imports", self.ignore_imports),
+            ("asyncify-ignore-indirect", self.ignore_indirect),
+            ("asyncify-asserts", self.asserts),
+            ("asyncify-verbose", self.verbose),
+            ("asyncify-relocatable", self.relocatable),
+            ("asyncify-in-secondary-memory", self.in_secondary_memory),
+        ];
+        for (argument, value) in flags.iter() {
+            if *value {
+                arguments.push((argument.to_string(), "1".to_string()));
+            }
+        }
+
+        if let Some(size) = self.secondary_memory_size {
+            let argument = "asyncify-secondary-memory-size";
+            if size > MAX_MEMORY_PAGES {
+                return Err(PassArgsError::InvalidValue {
+                    argument,
+                    value: size.to_string(),
+                });
+            }
+            if self.in_secondary_memory {
+                arguments.push((argument.to_string(), size.to_string()));
+            }
+        }
+
+        Ok(arguments)
+    }
+}
+
+/// Typed pass arguments.
+impl OptimizationOptions {
+    /// Adds a pass to [`Passes::more_passes`](crate::Passes::more_passes),
+    /// and its arguments to [`PassOptions::arguments`](crate::PassOptions::arguments).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the arguments are invalid,
+    /// in which case the options are unchanged.
+    pub fn add_pass_with_args(&mut self, args: impl PassArgs) -> Result<&mut Self, PassArgsError> {
+        let arguments = args.to_arguments()?;
+
+        for (key, value) in arguments {
+            self.set_pass_arg(&key, &value);
+        }
+
+        Ok(self.add_pass(args.pass()))
+    }
+}
+
+fn check_name(argument: &'static str, name: &str) -> Result<(), PassArgsError> {
+    if name.is_empty() {
+        Err(invalid_name(argument, name))
+    } else {
+        Ok(())
+    }
+}
+
+/// Names in comma-separated lists can't contain commas,
+/// and a list starting with `@` is read from a file.
+fn check_list_name(argument: &'static str, name: &str) -> Result<(), PassArgsError> {
+    check_name(argument, name)?;
+    if name.contains(',') || name.starts_with('@') {
+        Err(invalid_name(argument, name))
+    } else {
+        Ok(())
+    }
+}
+
+fn invalid_name(argument: &'static str, name: &str) -> PassArgsError {
+    PassArgsError::InvalidName {
+        argument,
+        name: name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_set_globals_args() {
+        let args = SetGlobalsArgs {
+            globals: vec![("a".to_string(), 1), ("b".to_string(), -2)],
+        };
+        let arguments = args.to_arguments().unwrap();
+        assert_eq!(
+            arguments,
+            vec![("set-globals".to_string(), "a=1,b=-2".to_string())]
+        );
+
+        let args = SetGlobalsArgs {
+            globals: vec![("a=b".to_string(), 1)],
+        };
+        assert!(matches!(
+            args.to_arguments(),
+            Err(PassArgsError::InvalidName { .. })
+        ));
+    }
+
+    #[test]
+    fn test_asyncify_args() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+
+        let args = AsyncifyArgs {
+            imports: vec!["env.sleep".to_string()],
+            remove_list: vec!["foo".to_string(), "bar*".to_string()],
+            ignore_indirect: true,
+            ..AsyncifyArgs::default()
+        };
+        opts.add_pass_with_args(args).unwrap();
+
+        let arguments = &opts.passopts.arguments;
+        assert_eq!(arguments["asyncify-imports"], "env.sleep");
+        assert_eq!(arguments["asyncify-removelist"], "foo,bar*");
+        assert_eq!(arguments["asyncify-ignore-indirect"], "1");
+        assert!(!arguments.contains_key("asyncify-asserts"));
+        assert!(matches!(opts.passes.more_passes[..], [Pass::Asyncify]));
+
+        let args = AsyncifyArgs {
+            imports: vec!["sleep".to_string()],
+            ..AsyncifyArgs::default()
+        };
+        assert!(opts.add_pass_with_args(args).is_err());
+        assert_eq!(opts.passes.more_passes.len(), 1);
+
+        let args = AsyncifyArgs {
+            only_list: vec!["foo".to_string()],
+            add_list: vec!["bar".to_string()],
+            ..AsyncifyArgs::default()
+        };
+        assert!(matches!(
+            args.to_arguments(),
+            Err(PassArgsError::Conflicting {
+                argument: "asyncify-onlylist",
+                other: "asyncify-addlist",
+            })
+        ));
+    }
+
+    #[test]
+    fn test_asyncify_secondary_memory_args() {
+        let args = AsyncifyArgs {
+            in_secondary_memory: true,
+            secondary_memory_size: Some(2),
+            ..AsyncifyArgs::default()
+        };
+        let arguments = args.to_arguments().unwrap();
+        assert_eq!(
+            arguments,
+            vec![
+                ("asyncify-in-secondary-memory".to_string(), "1".to_string()),
+                (
+                    "asyncify-secondary-memory-size".to_string(),
+                    "2".to_string()
+                ),
+            ]
+        );
+
+        let args = AsyncifyArgs {
+            in_secondary_memory: true,
+            secondary_memory_size: Some(MAX_MEMORY_PAGES + 1),
+            ..AsyncifyArgs::default()
+        };
+        assert!(matches!(
+            args.to_arguments(),
+            Err(PassArgsError::InvalidValue { .. })
+        ));
+    }
+}
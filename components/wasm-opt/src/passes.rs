@@ -1,13 +1,18 @@
-use crate::base::pass_registry;
+use crate::base::{pass_registry, PassRunner};
+use std::fmt;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
+use thiserror::Error;
 
 /// A Binaryen optimization pass.
 ///
 /// These have the same names as given on the command line to
 /// `wasm-opt`, but with Rust capitalization conventions.
-// Keep these in the same order as PassRegistry::registerPasses
+// Keep these in the same order as PassRegistry::registerPasses.
+// The `all_passes_correct` test checks them against the registry.
 #[non_exhaustive]
-#[derive(Clone, Debug, EnumIter)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, EnumIter)]
 pub enum Pass {
     /// Lower unaligned loads and stores to smaller aligned ones.
     AlignmentLowering,
@@ -17,7 +22,7 @@ pub enum Pass {
     AvoidReinterprets,
     /// Removes arguments to calls in an lto-like manner.
     Dae,
-    /// Removes arguments to calls in an lto-like manner, and optimizes where removed.
+    /// Removes arguments to calls in an lto-like manner, and optimizes where we removed.
     DaeOptimizing,
     /// Refine and merge abstract (never-created) types.
     AbstractTypeRefining,
@@ -73,17 +78,15 @@ pub enum Pass {
     GenerateStackIr,
     /// Refine the types of globals.
     GlobalRefining,
-    /// Globally optimize GC types.
-    Gto,
     /// Globally optimize struct values.
     Gsi,
-    /// Grand unified flow analyses.
-    ///
-    /// Optimize the entire program using information about what content can actually appear in each location.
+    /// Globally optimize GC types.
+    Gto,
+    /// Grand Unified Flow Analysis: optimize the entire program using information about what content can actually appear in each location.
     Gufa,
     /// GUFA plus add casts for all inferences.
     GufaCastAll,
-    /// Gufa plus local optimizations in functions we modified.
+    /// GUFA plus local optimizations in functions we modified.
     GufaOptimizing,
     /// Apply more specific subtypes to type fields where possible.
     TypeRefining,
@@ -151,7 +154,7 @@ pub enum Pass {
     MultiMemoryLoweringWithBoundsChecks,
     /// Name list.
     Nm,
-    /// (Re)name all heap types.
+    /// (re)name all heap types.
     NameTypes,
     /// Reduces calls to code that only runs once.
     OnceReduction,
@@ -175,7 +178,7 @@ pub enum Pass {
     OptimizeForJs,
     /// Computes compile-time evaluatable expressions.
     Precompute,
-    /// Computes compile-time evaluatable expressions and propagates.
+    /// Computes compile-time evaluatable expressions and propagates them through locals.
     PrecomputePropagate,
     /// Print in s-expression format.
     Print,
@@ -189,7 +192,7 @@ pub enum Pass {
     PrintCallGraph,
     /// Print a map of function indexes to names.
     PrintFunctionMap,
-    /// (Alias for print-function-map).
+    /// (alias for print-function-map).
     Symbolmap,
     /// Print out Stack IR (useful for internal debugging).
     PrintStackIr,
@@ -216,7 +219,7 @@ pub enum Pass {
     /// Sorts globals by access frequency.
     ReorderGlobals,
     /// Sorts locals by access frequency.
-    RecorderLocals,
+    ReorderLocals,
     /// Re-optimize control flow using the relooper algorithm.
     Rereloop,
     /// Remove redundant local.sets.
@@ -231,7 +234,7 @@ pub enum Pass {
     SignaturePruning,
     /// Apply more specific subtypes to signature types where possible.
     SignatureRefining,
-    /// Lower sign-ext operations to wasm mvp.
+    /// Lower sign-ext operations to wasm mvp and disable the sign extension feature.
     SignextLowering,
     /// Miscellaneous globals-related optimizations.
     SimplifyGlobals,
@@ -272,7 +275,7 @@ pub enum Pass {
     /// Strip EH instructions.
     StripEh,
     /// Strip the wasm target features section.
-    StripTargetFeatuers,
+    StripTargetFeatures,
     /// Replace trapping operations with clamping semantics.
     TrapModeClamp,
     /// Replace trapping operations with js semantics.
@@ -285,158 +288,265 @@ pub enum Pass {
     Untee,
     /// Removes obviously unneeded code.
     Vacuum,
+    /// A pass that is not listed here, by name.
+    ///
+    /// Create with [`Pass::custom`], which checks that the pass
+    /// is registered with Binaryen.
+    #[strum(disabled)]
+    Custom(String),
 }
 
 impl Pass {
     /// Returns the name of the pass.
     ///
     /// This is the same name used by Binaryen to identify the pass on the command line.
-    pub fn name(&self) -> &'static str {
-        use Pass::*;
+    pub fn name(&self) -> &str {
         match self {
-            AlignmentLowering => "alignment-lowering",
-            Asyncify => "asyncify",
-            AvoidReinterprets => "avoid-reinterprets",
-            Dae => "dae",
-            DaeOptimizing => "dae-optimizing",
-            AbstractTypeRefining => "abstract-type-refining",
-            CoalesceLocals => "coalesce-locals",
-            CoalesceLocalsLearning => "coalesce-locals-learning",
-            CodePushing => "code-pushing",
-            CodeFolding => "code-folding",
-            ConstHoisting => "const-hoisting",
-            Cfp => "cfp",
-            Dce => "dce",
-            Dealign => "dealign",
-            DeNan => "denan",
-            DiscardGlobalEffects => "discard-global-effects",
-            Directize => "directize",
-            Dfo => "dfo",
-            DwarfDump => "dwarfdump",
-            DuplicateImportElimination => "duplicate-import-elimination",
-            DuplicateFunctionElimination => "duplicate-function-elimination",
-            EmitTargetFeatures => "emit-target-features",
-            ExtractFunction => "extract-function",
-            ExtractFunctionIndex => "extract-function-index",
-            Flatten => "flatten",
-            FpCastEmu => "fpcast-emu",
-            FuncMetrics => "func-metrics",
-            GenerateDyncalls => "generate-dyncalls",
-            GenerateI64Dyncalls => "generate-i64-dyncalls",
-            GenerateGlobalEffects => "generate-global-effects",
-            GenerateStackIr => "generate-stack-ir",
-            GlobalRefining => "global-refining",
-            Gto => "gto",
-            Gsi => "gsi",
-            Gufa => "gufa",
-            GufaCastAll => "gufa-cast-all",
-            GufaOptimizing => "gufa-optimizing",
-            TypeRefining => "type-refining",
-            Heap2Local => "heap2local",
-            InlineMain => "inline-main",
-            Inlining => "inlining",
-            InliningOptimizing => "inlining-optimizing",
-            IntrinsicLowering => "intrinsic-lowering",
-            Jspi => "jspi",
-            LegalizeJsInterface => "legalize-js-interface",
-            LegalizeJsInterfaceMinimally => "legalize-js-interface-minimally",
-            LocalCse => "local-cse",
-            LocalSubtyping => "local-subtyping",
-            LogExecution => "log-execution",
-            I64ToI32Lowering => "i64-to-i32-lowering",
-            InstrumentLocals => "instrument-locals",
-            InstrumentMemory => "instrument-memory",
-            Licm => "licm",
-            LimitSegments => "limit-segments",
-            Memory64Lowering => "memory64-lowering",
-            MemoryPacking => "memory-packing",
-            MergeBlocks => "merge-blocks",
-            MergeSimilarFunctions => "merge-similar-functions",
-            MergeLocals => "merge-locals",
-            Metrics => "metrics",
-            MinifyImports => "minify-imports",
-            MinifyImportsAndExports => "minify-imports-and-exports",
-            MinifyImportsAndExportsAndModules => "minify-imports-and-exports-and-modules",
-            ModAsyncifyAlwaysAndOnlyUnwind => "mod-asyncify-always-and-only-unwind",
-            ModAsyncifyNeverUnwind => "mod-asyncify-never-unwind",
-            Monomorphize => "monomorphize",
-            MonomorphizeAlways => "monomorphize-always",
-            MultiMemoryLowering => "multi-memory-lowering",
-            MultiMemoryLoweringWithBoundsChecks => "multi-memory-lowering-with-bounds-checks",
-            Nm => "nm",
-            NameTypes => "name-types",
-            OnceReduction => "once-reduction",
-            OptimizeAddedConstants => "optimize-added-constants",
-            OptimizeAddedConstantsPropagate => "optimize-added-constants-propagate",
-            OptimizeCasts => "optimize-casts",
-            OptimizeInstructions => "optimize-instructions",
-            OptimizeStackIr => "optimize-stack-ir",
-            PickLoadSigns => "pick-load-signs",
-            Poppify => "poppify",
-            PostEmscripten => "post-emscripten",
-            OptimizeForJs => "optimize-for-js",
-            Precompute => "precompute",
-            PrecomputePropagate => "precompute-propagate",
-            Print => "print",
-            PrintMinified => "print-minified",
-            PrintFeatures => "print-features",
-            PrintFull => "print-full",
-            PrintCallGraph => "print-call-graph",
-            PrintFunctionMap => "print-function-map",
-            Symbolmap => "symbolmap",
-            PrintStackIr => "print-stack-ir",
-            RemoveNonJsOps => "remove-non-js-ops",
-            RemoveImports => "remove-imports",
-            RemoveMemory => "remove-memory",
-            RemoveUnusedBrs => "remove-unused-brs",
-            RemoveUnusedModuleElements => "remove-unused-module-elements",
-            RemoveUnusedNonfunctionModuleElements => "remove-unused-nonfunction-module-elements",
-            RemoveUnusedNames => "remove-unused-names",
-            RemoveUnusedTypes => "remove-unused-types",
-            ReorderFunctionsByName => "reorder-functions-by-name",
-            ReorderFunctions => "reorder-functions",
-            ReorderGlobals => "reorder-globals",
-            RecorderLocals => "reorder-locals",
-            Rereloop => "rereloop",
-            Rse => "rse",
-            Roundtrip => "roundtrip",
-            SafeHeap => "safe-heap",
-            SetGlobals => "set-globals",
-            SignaturePruning => "signature-pruning",
-            SignatureRefining => "signature-refining",
-            SignextLowering => "signext-lowering",
-            SimplifyGlobals => "simplify-globals",
-            SimplifyGlobalsOptimizing => "simplify-globals-optimizing",
-            SimplifyLocals => "simplify-locals",
-            SimplifyLocalsNonesting => "simplify-locals-nonesting",
-            SimplifyLocalsNotee => "simplify-locals-notee",
-            SimplifyLocalsNostructure => "simplify-locals-nostructure",
-            SimplifyLocalsNoteeNostructure => "simplify-locals-notee-nostructure",
-            Souperify => "souperify",
-            SouperifySingleUse => "souperify-single-use",
-            SpillPointers => "spill-pointers",
-            StubUnsupportedJs => "stub-unsupported-js",
-            Ssa => "ssa",
-            SsaNomerge => "ssa-nomerge",
-            Strip => "strip",
-            StackCheck => "stack-check",
-            StripDebug => "strip-debug",
-            StripDwarf => "strip-dwarf",
-            StripProducers => "strip-producers",
-            StripEh => "strip-eh",
-            StripTargetFeatuers => "strip-target-features",
-            TrapModeClamp => "trap-mode-clamp",
-            TrapModeJs => "trap-mode-js",
-            TypeMerging => "type-merging",
-            TypeSsa => "type-ssa",
-            Untee => "untee",
-            Vacuum => "vacuum",
+            Pass::AlignmentLowering => "alignment-lowering",
+            Pass::Asyncify => "asyncify",
+            Pass::AvoidReinterprets => "avoid-reinterprets",
+            Pass::Dae => "dae",
+            Pass::DaeOptimizing => "dae-optimizing",
+            Pass::AbstractTypeRefining => "abstract-type-refining",
+            Pass::CoalesceLocals => "coalesce-locals",
+            Pass::CoalesceLocalsLearning => "coalesce-locals-learning",
+            Pass::CodePushing => "code-pushing",
+            Pass::CodeFolding => "code-folding",
+            Pass::ConstHoisting => "const-hoisting",
+            Pass::Cfp => "cfp",
+            Pass::Dce => "dce",
+            Pass::Dealign => "dealign",
+            Pass::DeNan => "denan",
+            Pass::Directize => "directize",
+            Pass::DiscardGlobalEffects => "discard-global-effects",
+            Pass::Dfo => "dfo",
+            Pass::DwarfDump => "dwarfdump",
+            Pass::DuplicateImportElimination => "duplicate-import-elimination",
+            Pass::DuplicateFunctionElimination => "duplicate-function-elimination",
+            Pass::EmitTargetFeatures => "emit-target-features",
+            Pass::ExtractFunction => "extract-function",
+            Pass::ExtractFunctionIndex => "extract-function-index",
+            Pass::Flatten => "flatten",
+            Pass::FpCastEmu => "fpcast-emu",
+            Pass::FuncMetrics => "func-metrics",
+            Pass::GenerateDyncalls => "generate-dyncalls",
+            Pass::GenerateI64Dyncalls => "generate-i64-dyncalls",
+            Pass::GenerateGlobalEffects => "generate-global-effects",
+            Pass::GenerateStackIr => "generate-stack-ir",
+            Pass::GlobalRefining => "global-refining",
+            Pass::Gsi => "gsi",
+            Pass::Gto => "gto",
+            Pass::Gufa => "gufa",
+            Pass::GufaCastAll => "gufa-cast-all",
+            Pass::GufaOptimizing => "gufa-optimizing",
+            Pass::TypeRefining => "type-refining",
+            Pass::Heap2Local => "heap2local",
+            Pass::InlineMain => "inline-main",
+            Pass::Inlining => "inlining",
+            Pass::InliningOptimizing => "inlining-optimizing",
+            Pass::IntrinsicLowering => "intrinsic-lowering",
+            Pass::Jspi => "jspi",
+            Pass::LegalizeJsInterface => "legalize-js-interface",
+            Pass::LegalizeJsInterfaceMinimally => "legalize-js-interface-minimally",
+            Pass::LocalCse => "local-cse",
+            Pass::LocalSubtyping => "local-subtyping",
+            Pass::LogExecution => "log-execution",
+            Pass::I64ToI32Lowering => "i64-to-i32-lowering",
+            Pass::InstrumentLocals => "instrument-locals",
+            Pass::InstrumentMemory => "instrument-memory",
+            Pass::Licm => "licm",
+            Pass::LimitSegments => "limit-segments",
+            Pass::Memory64Lowering => "memory64-lowering",
+            Pass::MemoryPacking => "memory-packing",
+            Pass::MergeBlocks => "merge-blocks",
+            Pass::MergeSimilarFunctions => "merge-similar-functions",
+            Pass::MergeLocals => "merge-locals",
+            Pass::Metrics => "metrics",
+            Pass::MinifyImports => "minify-imports",
+            Pass::MinifyImportsAndExports => "minify-imports-and-exports",
+            Pass::MinifyImportsAndExportsAndModules => "minify-imports-and-exports-and-modules",
+            Pass::ModAsyncifyAlwaysAndOnlyUnwind => "mod-asyncify-always-and-only-unwind",
+            Pass::ModAsyncifyNeverUnwind => "mod-asyncify-never-unwind",
+            Pass::Monomorphize => "monomorphize",
+            Pass::MonomorphizeAlways => "monomorphize-always",
+            Pass::MultiMemoryLowering => "multi-memory-lowering",
+            Pass::MultiMemoryLoweringWithBoundsChecks => "multi-memory-lowering-with-bounds-checks",
+            Pass::Nm => "nm",
+            Pass::NameTypes => "name-types",
+            Pass::OnceReduction => "once-reduction",
+            Pass::OptimizeAddedConstants => "optimize-added-constants",
+            Pass::OptimizeAddedConstantsPropagate => "optimize-added-constants-propagate",
+            Pass::OptimizeCasts => "optimize-casts",
+            Pass::OptimizeInstructions => "optimize-instructions",
+            Pass::OptimizeStackIr => "optimize-stack-ir",
+            Pass::PickLoadSigns => "pick-load-signs",
+            Pass::Poppify => "poppify",
+            Pass::PostEmscripten => "post-emscripten",
+            Pass::OptimizeForJs => "optimize-for-js",
+            Pass::Precompute => "precompute",
+            Pass::PrecomputePropagate => "precompute-propagate",
+            Pass::Print => "print",
+            Pass::PrintMinified => "print-minified",
+            Pass::PrintFeatures => "print-features",
+            Pass::PrintFull => "print-full",
+            Pass::PrintCallGraph => "print-call-graph",
+            Pass::PrintFunctionMap => "print-function-map",
+            Pass::Symbolmap => "symbolmap",
+            Pass::PrintStackIr => "print-stack-ir",
+            Pass::RemoveNonJsOps => "remove-non-js-ops",
+            Pass::RemoveImports => "remove-imports",
+            Pass::RemoveMemory => "remove-memory",
+            Pass::RemoveUnusedBrs => "remove-unused-brs",
+            Pass::RemoveUnusedModuleElements => "remove-unused-module-elements",
+            Pass::RemoveUnusedNonfunctionModuleElements => {
+                "remove-unused-nonfunction-module-elements"
+            }
+            Pass::RemoveUnusedNames => "remove-unused-names",
+            Pass::RemoveUnusedTypes => "remove-unused-types",
+            Pass::ReorderFunctionsByName => "reorder-functions-by-name",
+            Pass::ReorderFunctions => "reorder-functions",
+            Pass::ReorderGlobals => "reorder-globals",
+            Pass::ReorderLocals => "reorder-locals",
+            Pass::Rereloop => "rereloop",
+            Pass::Rse => "rse",
+            Pass::Roundtrip => "roundtrip",
+            Pass::SafeHeap => "safe-heap",
+            Pass::SetGlobals => "set-globals",
+            Pass::SignaturePruning => "signature-pruning",
+            Pass::SignatureRefining => "signature-refining",
+            Pass::SignextLowering => "signext-lowering",
+            Pass::SimplifyGlobals => "simplify-globals",
+            Pass::SimplifyGlobalsOptimizing => "simplify-globals-optimizing",
+            Pass::SimplifyLocals => "simplify-locals",
+            Pass::SimplifyLocalsNonesting => "simplify-locals-nonesting",
+            Pass::SimplifyLocalsNotee => "simplify-locals-notee",
+            Pass::SimplifyLocalsNostructure => "simplify-locals-nostructure",
+            Pass::SimplifyLocalsNoteeNostructure => "simplify-locals-notee-nostructure",
+            Pass::Souperify => "souperify",
+            Pass::SouperifySingleUse => "souperify-single-use",
+            Pass::SpillPointers => "spill-pointers",
+            Pass::StubUnsupportedJs => "stub-unsupported-js",
+            Pass::Ssa => "ssa",
+            Pass::SsaNomerge => "ssa-nomerge",
+            Pass::Strip => "strip",
+            Pass::StackCheck => "stack-check",
+            Pass::StripDebug => "strip-debug",
+            Pass::StripDwarf => "strip-dwarf",
+            Pass::StripProducers => "strip-producers",
+            Pass::StripEh => "strip-eh",
+            Pass::StripTargetFeatures => "strip-target-features",
+            Pass::TrapModeClamp => "trap-mode-clamp",
+            Pass::TrapModeJs => "trap-mode-js",
+            Pass::TypeMerging => "type-merging",
+            Pass::TypeSsa => "type-ssa",
+            Pass::Untee => "untee",
+            Pass::Vacuum => "vacuum",
+            Pass::Custom(name) => name,
+        }
+    }
+}
+
+/// An error resulting from a pass name that is not registered with Binaryen.
+#[derive(Error, Debug)]
+#[error("Unknown pass: {name}")]
+pub struct UnknownPassError {
+    /// The name of the pass.
+    pub name: String,
+}
+
+impl Pass {
+    /// Create a [`Pass::Custom`] pass by name.
+    ///
+    /// This is for passes that are registered with Binaryen
+    /// but not listed in this enum, including hidden test passes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no pass of this name is registered with Binaryen.
+    pub fn custom(name: &str) -> Result<Pass, UnknownPassError> {
+        if pass_registry::is_registered(name) {
+            Ok(Pass::Custom(name.to_string()))
+        } else {
+            Err(unknown_pass(name))
+        }
+    }
+
+    /// Returns `false` for a [`Pass::Custom`] pass that is not registered with Binaryen.
+    ///
+    /// Binaryen aborts when asked to run such a pass.
+    pub(crate) fn is_registered(&self) -> bool {
+        match self {
+            Pass::Custom(name) => pass_registry::is_registered(name),
+            _ => true,
         }
     }
 
     /// Get Binaryen's description of the pass.
-    pub fn description(&self) -> String {
-        // NB: This will abort if the name is invalid
-        pass_registry::get_pass_description(self.name())
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for a [`Pass::Custom`] pass
+    /// that is not registered with Binaryen.
+    pub fn description(&self) -> Result<String, UnknownPassError> {
+        pass_registry::get_pass_description(self.name()).ok_or_else(|| unknown_pass(self.name()))
+    }
+
+    /// Returns `true` if Binaryen hides the pass from `wasm-opt --help`.
+    ///
+    /// Binaryen's internal test passes are hidden,
+    /// and can only be run as [`Pass::Custom`] passes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for a [`Pass::Custom`] pass
+    /// that is not registered with Binaryen.
+    pub fn is_hidden(&self) -> Result<bool, UnknownPassError> {
+        pass_registry::is_pass_hidden(self.name()).ok_or_else(|| unknown_pass(self.name()))
+    }
+
+    /// Returns `true` if running the pass removes debug info from the module.
+    pub fn removes_debug_info(&self) -> bool {
+        PassRunner::pass_removes_debug_info(self.name())
+    }
+}
+
+/// Parses the command line name of a pass.
+///
+/// Names of passes that are registered with Binaryen,
+/// but not listed in [`Pass`], parse as [`Pass::Custom`].
+impl FromStr for Pass {
+    type Err = UnknownPassError;
+
+    fn from_str(name: &str) -> Result<Pass, UnknownPassError> {
+        Pass::iter()
+            .find(|pass| pass.name() == name)
+            .map(Ok)
+            .unwrap_or_else(|| Pass::custom(name))
+    }
+}
+
+/// Displays the command line name of a pass.
+impl fmt::Display for Pass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn unknown_pass(name: &str) -> UnknownPassError {
+    UnknownPassError {
+        name: name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pass_names_round_trip() {
+        for pass in Pass::iter() {
+            let name = pass.to_string();
+            assert_eq!(name.parse::<Pass>().unwrap(), pass);
+        }
     }
 }
@@ -0,0 +1,163 @@
+//! Building and parsing `Pipeline`s.
+
+use crate::api::*;
+use crate::passes::UnknownPassError;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An error resulting from parsing a [`Pipeline`].
+#[derive(Error, Debug)]
+pub enum PipelineParseError {
+    /// An element of the pipeline is neither a pass name nor an `-O` level.
+    #[error("Unknown pass or optimization level in pipeline: {name}")]
+    UnknownStage { name: String },
+}
+
+impl Pipeline {
+    /// Create an empty pipeline.
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Add the default optimization passes with the given levels.
+    pub fn add_default_passes(
+        &mut self,
+        optimize_level: OptimizeLevel,
+        shrink_level: ShrinkLevel,
+    ) -> &mut Self {
+        self.stages.push(Stage::DefaultPasses {
+            optimize_level,
+            shrink_level,
+        });
+        self
+    }
+
+    /// Add one phase of the default optimization passes with the given levels.
+    pub fn add_default_phase(
+        &mut self,
+        phase: DefaultPhase,
+        optimize_level: OptimizeLevel,
+        shrink_level: ShrinkLevel,
+    ) -> &mut Self {
+        self.stages.push(Stage::DefaultPhase {
+            phase,
+            optimize_level,
+            shrink_level,
+        });
+        self
+    }
+
+    /// Set the levels of the individual passes added after this.
+    pub fn set_levels(
+        &mut self,
+        optimize_level: OptimizeLevel,
+        shrink_level: ShrinkLevel,
+    ) -> &mut Self {
+        self.stages.push(Stage::Levels {
+            optimize_level,
+            shrink_level,
+        });
+        self
+    }
+
+    /// Add a single pass.
+    pub fn add_pass(&mut self, pass: Pass) -> &mut Self {
+        self.stages.push(Stage::Pass(pass));
+        self
+    }
+
+    /// Iterate over the individual passes of the pipeline.
+    pub fn passes(&self) -> impl Iterator<Item = &Pass> {
+        self.stages.iter().filter_map(|stage| match stage {
+            Stage::Pass(pass) => Some(pass),
+            Stage::DefaultPasses { .. } | Stage::DefaultPhase { .. } | Stage::Levels { .. } => None,
+        })
+    }
+}
+
+impl FromStr for Pipeline {
+    type Err = PipelineParseError;
+
+    fn from_str(s: &str) -> Result<Pipeline, PipelineParseError> {
+        let mut pipeline = Pipeline::new();
+
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let (optimize_level, shrink_level) = match name {
+                "O0" => {
+                    pipeline.set_levels(OptimizeLevel::Level0, ShrinkLevel::Level0);
+                    continue;
+                }
+                "O1" => (OptimizeLevel::Level1, ShrinkLevel::Level0),
+                "O2" => (OptimizeLevel::Level2, ShrinkLevel::Level0),
+                "O3" => (OptimizeLevel::Level3, ShrinkLevel::Level0),
+                "O4" => (OptimizeLevel::Level4, ShrinkLevel::Level0),
+                "O" | "Os" => (OptimizeLevel::Level2, ShrinkLevel::Level1),
+                "Oz" => (OptimizeLevel::Level2, ShrinkLevel::Level2),
+                _ => {
+                    let pass = name.parse::<Pass>().map_err(|UnknownPassError { name }| {
+                        PipelineParseError::UnknownStage { name }
+                    })?;
+                    pipeline.add_pass(pass);
+                    continue;
+                }
+            };
+
+            pipeline.add_default_passes(optimize_level, shrink_level);
+        }
+
+        Ok(pipeline)
+    }
+}
+
+impl OptimizationOptions {
+    /// The individual passes that will run,
+    /// from either the pipeline or [`Passes::more_passes`].
+    pub(crate) fn individual_passes(&self) -> Vec<Pass> {
+        if let Some(pipeline) = &self.pipeline {
+            pipeline.passes().cloned().collect()
+        } else {
+            self.passes.more_passes.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_pipeline() {
+        let pipeline: Pipeline = "dce, O2,inlining-optimizing,Oz,O0,".parse().unwrap();
+
+        assert_eq!(pipeline.stages.len(), 5);
+        assert!(matches!(pipeline.stages[0], Stage::Pass(Pass::Dce)));
+        assert!(matches!(
+            pipeline.stages[1],
+            Stage::DefaultPasses {
+                optimize_level: OptimizeLevel::Level2,
+                shrink_level: ShrinkLevel::Level0,
+            }
+        ));
+        assert!(matches!(
+            pipeline.stages[2],
+            Stage::Pass(Pass::InliningOptimizing)
+        ));
+        assert!(matches!(
+            pipeline.stages[3],
+            Stage::DefaultPasses {
+                optimize_level: OptimizeLevel::Level2,
+                shrink_level: ShrinkLevel::Level2,
+            }
+        ));
+        assert!(matches!(
+            pipeline.stages[4],
+            Stage::Levels {
+                optimize_level: OptimizeLevel::Level0,
+                shrink_level: ShrinkLevel::Level0,
+            }
+        ));
+
+        let res = "dce,O5".parse::<Pipeline>();
+        assert!(matches!(res, Err(PipelineParseError::UnknownStage { .. })));
+    }
+}
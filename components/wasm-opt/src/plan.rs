@@ -0,0 +1,246 @@
+//! Listing the passes that would run, without running them.
+
+use crate::api::*;
+use crate::base::Module;
+use crate::pass_args::PassArgs;
+
+/// A pass that [`OptimizationOptions::run`] would run,
+/// as returned by [`OptimizationOptions::planned_passes`].
+#[derive(Clone, Debug)]
+pub struct PlannedPass {
+    /// The name of the pass, as given on the `wasm-opt` command line.
+    pub name: String,
+    /// The optimize level the pass runs with.
+    pub optimize_level: OptimizeLevel,
+    /// The shrink level the pass runs with.
+    pub shrink_level: ShrinkLevel,
+    /// The pass arguments from [`PassOptions::arguments`] that the pass reads,
+    /// sorted by key.
+    ///
+    /// For [`Pass::Asyncify`] run by [`OptimizationOptions::asyncify`]
+    /// these include the arguments from the [`AsyncifyConfig`](crate::asyncify::AsyncifyConfig).
+    pub arguments: Vec<(String, String)>,
+}
+
+/// The pass arguments read by Binaryen's passes,
+/// with the passes that read them.
+const PASS_ARGUMENTS: &[(&str, &[&str])] = &[
+    ("asyncify-addlist", &["asyncify"]),
+    ("asyncify-asserts", &["asyncify"]),
+    ("asyncify-blacklist", &["asyncify"]),
+    ("asyncify-ignore-imports", &["asyncify"]),
+    ("asyncify-ignore-indirect", &["asyncify"]),
+    ("asyncify-imports", &["asyncify"]),
+    ("asyncify-in-secondary-memory", &["asyncify"]),
+    ("asyncify-onlylist", &["asyncify"]),
+    ("asyncify-relocatable", &["asyncify"]),
+    ("asyncify-removelist", &["asyncify"]),
+    ("asyncify-secondary-memory-size", &["asyncify"]),
+    ("asyncify-verbose", &["asyncify"]),
+    ("asyncify-whitelist", &["asyncify"]),
+    ("directize-initial-contents-immutable", &["directize"]),
+    ("extract-function", &["extract-function"]),
+    ("extract-function-index", &["extract-function-index"]),
+    ("jspi-exports", &["jspi"]),
+    ("jspi-imports", &["jspi"]),
+    ("jspi-split-module", &["jspi"]),
+    (
+        "legalize-js-interface-export-originals",
+        &["legalize-js-interface", "legalize-js-interface-minimally"],
+    ),
+    (
+        "legalize-js-interface-exported-helpers",
+        &["legalize-js-interface", "legalize-js-interface-minimally"],
+    ),
+    ("max-func-params", &["fpcast-emu"]),
+    ("post-emscripten-side-module", &["post-emscripten"]),
+    ("set-globals", &["set-globals"]),
+    ("stack-check-handler", &["stack-check"]),
+    ("symbolmap", &["print-function-map", "symbolmap"]),
+];
+
+impl OptimizationOptions {
+    /// List the passes that [`OptimizationOptions::run`] would run, in order.
+    ///
+    /// This expands the default optimization passes for the configured
+    /// levels and features, without loading a module.
+    ///
+    /// The default passes can depend on the module being optimized &mdash;
+    /// some passes are skipped for modules with DWARF debug info &mdash;
+    /// so this is the list for a module without DWARF.
+    /// With [`FeatureBaseline::Detect`] it is the list for a module
+    /// that uses all features.
+    ///
+    /// The list includes the passes run for other options,
+    /// in the order they run:
+    /// the lowering passes of
+    /// [`OptimizationOptions::lower_unsupported_features`] and [`LoweringOptions`],
+    /// the cleanup after [`OptimizationOptions::metadce`],
+    /// and [`OptimizationOptions::asyncify`] before the optimization passes;
+    /// then the NaN canonicalization of [`OptimizationOptions::determinism`],
+    /// [`WriterOptions::emit_target_features`] and [`StackIrOptions`] after them.
+    /// Which features are lowered depends on the module,
+    /// so the list has the lowering passes for a module
+    /// that uses every feature the configured feature set lacks.
+    ///
+    /// When [`OptimizationOptions::converge`] is set,
+    /// the optimization passes are run repeatedly.
+    ///
+    /// Passes in [`PassOptions::passes_to_skip`] are not listed.
+    ///
+    /// An [`AsyncifyConfig`](crate::asyncify::AsyncifyConfig) that can't be
+    /// expressed as pass arguments is listed without arguments;
+    /// [`OptimizationOptions::check`] reports it as an error.
+    ///
+    /// [`Pass::Custom`] passes that are not registered with Binaryen
+    /// are not listed; [`OptimizationOptions::check`] reports them.
+    pub fn planned_passes(&self) -> Vec<PlannedPass> {
+        let mut m = Module::new();
+        self.apply_features(&mut m);
+
+        let levels = (self.passopts.optimize_level, self.passopts.shrink_level);
+        let mut planned = vec![];
+
+        for pass in self.passes_before_optimizing(&m) {
+            // Asyncify run for `OptimizationOptions::asyncify` gets the config's arguments.
+            // `check` reports a config that has none.
+            let extra_arguments = match (&pass, &self.asyncify) {
+                (Pass::Asyncify, Some(asyncify)) => asyncify.to_arguments().unwrap_or_default(),
+                _ => vec![],
+            };
+            self.plan_pass(
+                &mut planned,
+                pass.name().to_string(),
+                levels,
+                extra_arguments,
+            );
+        }
+
+        self.build_pass_runners(&mut m, &mut |pass_runner, optimize_level, shrink_level| {
+            for name in pass_runner.pass_names() {
+                self.plan_pass(&mut planned, name, (optimize_level, shrink_level), vec![]);
+            }
+        });
+
+        for pass in self.passes_after_optimizing() {
+            self.plan_pass(&mut planned, pass.name().to_string(), levels, vec![]);
+        }
+
+        planned
+    }
+
+    /// The passes run for other options before the optimization passes.
+    fn passes_before_optimizing(&self, m: &Module) -> Vec<Pass> {
+        let mut passes = vec![];
+
+        let detect = matches!(self.features.baseline, FeatureBaseline::Detect);
+        if self.lower_unsupported_features && !detect {
+            let target = m.features();
+            passes.extend(
+                Feature::all_real()
+                    .iter()
+                    .filter(|feature| target & feature.to_base() as u32 == 0)
+                    .filter_map(|feature| self.feature_lowering_pass(*feature)),
+            );
+        }
+
+        passes.extend(self.requested_lowering_passes());
+
+        if self.metadce.is_some() {
+            passes.push(Pass::RemoveUnusedModuleElements);
+        }
+
+        if self.asyncify.is_some() {
+            passes.push(Pass::Asyncify);
+        }
+
+        passes
+    }
+
+    /// The passes run for other options after the optimization passes.
+    fn passes_after_optimizing(&self) -> Vec<Pass> {
+        let mut passes = vec![];
+
+        if let Some(determinism) = &self.determinism {
+            if determinism.canonicalizes_nans() {
+                passes.push(Pass::DeNan);
+            }
+        }
+
+        if self.writer.emit_target_features {
+            passes.push(Pass::EmitTargetFeatures);
+        }
+
+        if self.stack_ir.generate {
+            passes.push(Pass::GenerateStackIr);
+            if self.stack_ir.optimize {
+                passes.push(Pass::OptimizeStackIr);
+            }
+            if self.stack_ir.print {
+                passes.push(Pass::PrintStackIr);
+            }
+        }
+
+        passes
+    }
+
+    /// Add a pass to the plan, unless it is skipped.
+    ///
+    /// `extra_arguments` take precedence over [`PassOptions::arguments`].
+    fn plan_pass(
+        &self,
+        planned: &mut Vec<PlannedPass>,
+        name: String,
+        (optimize_level, shrink_level): (OptimizeLevel, ShrinkLevel),
+        extra_arguments: Vec<(String, String)>,
+    ) {
+        if self
+            .passopts
+            .passes_to_skip
+            .iter()
+            .any(|pass| pass.name() == name)
+        {
+            return;
+        }
+
+        let mut arguments: Vec<(String, String)> = self
+            .passopts
+            .arguments
+            .iter()
+            .filter(|(key, _)| !extra_arguments.iter().any(|(other, _)| other == *key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        arguments.extend(extra_arguments);
+        arguments.retain(|(key, _)| is_argument_for_pass(key, &name));
+        arguments.sort();
+
+        planned.push(PlannedPass {
+            name,
+            optimize_level,
+            shrink_level,
+            arguments,
+        });
+    }
+}
+
+fn is_argument_for_pass(key: &str, pass_name: &str) -> bool {
+    PASS_ARGUMENTS
+        .iter()
+        .any(|(argument, passes)| *argument == key && passes.contains(&pass_name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arguments_are_matched_exactly() {
+        assert!(is_argument_for_pass("extract-function", "extract-function"));
+        assert!(!is_argument_for_pass(
+            "extract-function-index",
+            "extract-function"
+        ));
+        assert!(is_argument_for_pass("symbolmap", "print-function-map"));
+        assert!(!is_argument_for_pass("asyncify-imports", "jspi"));
+    }
+}
@@ -1,78 +1,214 @@
-use crate::api::{OptimizationOptions, OptimizeLevel, ShrinkLevel};
+//! Named optimization presets.
+//!
+//! A [`Profile`] bundles the settings that select an optimization pipeline:
+//! the optimize and shrink levels, whether to run the default passes,
+//! inlining options, extra passes, and features.
+//! The built-in profiles correspond to the `-O` flags of `wasm-opt`,
+//! and custom profiles can be defined by modifying them.
+//!
+//! Profiles can be registered by name in a [`ProfileRegistry`],
+//! and then applied with [`OptimizationOptions::from_profile`],
+//! or by the `--profile <name>` argument to
+//! [`run_from_command_args_with_profiles`](crate::integration::run_from_command_args_with_profiles).
+//!
+//! ```
+//! use wasm_opt::profiles::{Profile, ProfileRegistry};
+//! use wasm_opt::{OptimizationOptions, Pass};
+//!
+//! let mut release_small = Profile::optimize_for_size_aggressively();
+//! release_small.passes.push(Pass::StripDebug);
+//!
+//! let mut profiles = ProfileRegistry::new();
+//! profiles.register("release-small", release_small);
+//!
+//! let profile = profiles.get("release-small").unwrap().clone();
+//! let opts = OptimizationOptions::from_profile(profile);
+//! ```
 
-#[derive(Eq, PartialEq, Debug)]
+use crate::api::{Feature, InliningOptions, OptimizationOptions, OptimizeLevel, Pass, ShrinkLevel};
+use std::collections::{HashMap, HashSet};
+
+/// A set of optimization settings that can be applied to [`OptimizationOptions`].
+#[derive(Clone, Debug)]
 pub struct Profile {
-    optimize_level: OptimizeLevel,
-    shrink_level: ShrinkLevel,
-    add_default_passes: bool,
+    /// The optimize level.
+    pub optimize_level: OptimizeLevel,
+    /// The shrink level.
+    pub shrink_level: ShrinkLevel,
+    /// Apply the default set of optimization passes.
+    pub add_default_passes: bool,
+    /// Inlining options.
+    ///
+    /// If `None`, the inlining options are left unchanged.
+    pub inlining: Option<InliningOptions>,
+    /// Passes to run after the default passes.
+    pub passes: Vec<Pass>,
+    /// Features to enable.
+    pub enabled_features: HashSet<Feature>,
+    /// Features to disable.
+    pub disabled_features: HashSet<Feature>,
 }
 
 impl Profile {
-    pub fn optimize_for_size() -> Profile {
+    fn from_levels(
+        optimize_level: OptimizeLevel,
+        shrink_level: ShrinkLevel,
+        add_default_passes: bool,
+    ) -> Profile {
         Profile {
-            optimize_level: OptimizeLevel::Level2,
-            shrink_level: ShrinkLevel::Level1,
-            add_default_passes: true,
+            optimize_level,
+            shrink_level,
+            add_default_passes,
+            inlining: None,
+            passes: vec![],
+            enabled_features: HashSet::new(),
+            disabled_features: HashSet::new(),
         }
     }
 
+    /// The profile of `wasm-opt -Os`.
+    pub fn optimize_for_size() -> Profile {
+        Profile::from_levels(OptimizeLevel::Level2, ShrinkLevel::Level1, true)
+    }
+
+    /// The profile of `wasm-opt -Oz`.
     pub fn optimize_for_size_aggressively() -> Profile {
-        Profile {
-            optimize_level: OptimizeLevel::Level2,
-            shrink_level: ShrinkLevel::Level2,
-            add_default_passes: true,
-        }
+        Profile::from_levels(OptimizeLevel::Level2, ShrinkLevel::Level2, true)
     }
 
+    /// The profile of `wasm-opt -O0`.
     pub fn opt_level_0() -> Profile {
-        Profile {
-            optimize_level: OptimizeLevel::Level0,
-            shrink_level: ShrinkLevel::Level0,
-            add_default_passes: false,
-        }
+        Profile::from_levels(OptimizeLevel::Level0, ShrinkLevel::Level0, false)
     }
 
+    /// The profile of `wasm-opt -O1`.
     pub fn opt_level_1() -> Profile {
-        Profile {
-            optimize_level: OptimizeLevel::Level1,
-            shrink_level: ShrinkLevel::Level0,
-            add_default_passes: true,
-        }
+        Profile::from_levels(OptimizeLevel::Level1, ShrinkLevel::Level0, true)
     }
 
+    /// The profile of `wasm-opt -O2`.
     pub fn opt_level_2() -> Profile {
-        Profile {
-            optimize_level: OptimizeLevel::Level2,
-            shrink_level: ShrinkLevel::Level0,
-            add_default_passes: true,
-        }
+        Profile::from_levels(OptimizeLevel::Level2, ShrinkLevel::Level0, true)
     }
 
+    /// The profile of `wasm-opt -O3`.
     pub fn opt_level_3() -> Profile {
-        Profile {
-            optimize_level: OptimizeLevel::Level3,
-            shrink_level: ShrinkLevel::Level0,
-            add_default_passes: true,
-        }
+        Profile::from_levels(OptimizeLevel::Level3, ShrinkLevel::Level0, true)
     }
 
+    /// The profile of `wasm-opt -O4`.
     pub fn opt_level_4() -> Profile {
-        Profile {
-            optimize_level: OptimizeLevel::Level4,
-            shrink_level: ShrinkLevel::Level0,
-            add_default_passes: true,
-        }
+        Profile::from_levels(OptimizeLevel::Level4, ShrinkLevel::Level0, true)
     }
 
+    /// Create an [`OptimizationOptions`] with this profile applied.
     pub fn into_opts(self) -> OptimizationOptions {
         let mut opts = OptimizationOptions::new_empty();
         self.apply_to_opts(&mut opts);
         opts
     }
 
+    /// Apply this profile on top of existing options.
+    ///
+    /// The levels and the default-pass flag are replaced,
+    /// the inlining options are replaced if set,
+    /// and the passes and features are added.
     pub fn apply_to_opts(self, opts: &mut OptimizationOptions) {
         opts.passopts.optimize_level = self.optimize_level;
         opts.passopts.shrink_level = self.shrink_level;
         opts.passes.add_default_passes = self.add_default_passes;
+
+        if let Some(inlining) = self.inlining {
+            opts.inlining = inlining;
+        }
+
+        opts.passes.more_passes.extend(self.passes);
+
+        for feature in self.enabled_features {
+            opts.enable_feature(feature);
+        }
+        for feature in self.disabled_features {
+            opts.disable_feature(feature);
+        }
+    }
+}
+
+/// A collection of named [`Profile`]s.
+///
+/// A new registry contains the built-in profiles,
+/// named after the `wasm-opt` flags that select them:
+/// `O0`, `O1`, `O2`, `O3`, `O4`, `Os` and `Oz`.
+#[derive(Clone, Debug)]
+pub struct ProfileRegistry {
+    profiles: HashMap<String, Profile>,
+}
+
+impl ProfileRegistry {
+    /// Create a registry containing the built-in profiles.
+    pub fn new() -> ProfileRegistry {
+        let mut registry = ProfileRegistry {
+            profiles: HashMap::new(),
+        };
+        registry.register("O0", Profile::opt_level_0());
+        registry.register("O1", Profile::opt_level_1());
+        registry.register("O2", Profile::opt_level_2());
+        registry.register("O3", Profile::opt_level_3());
+        registry.register("O4", Profile::opt_level_4());
+        registry.register("Os", Profile::optimize_for_size());
+        registry.register("Oz", Profile::optimize_for_size_aggressively());
+        registry
+    }
+
+    /// Register a profile, replacing any profile of the same name.
+    pub fn register(&mut self, name: &str, profile: Profile) -> &mut Self {
+        self.profiles.insert(name.to_string(), profile);
+        self
+    }
+
+    /// Get a profile by name.
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    /// Iterate over the registered profile names.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+}
+
+impl Default for ProfileRegistry {
+    fn default() -> ProfileRegistry {
+        ProfileRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_custom_profile() {
+        let mut profile = Profile::optimize_for_size_aggressively();
+        profile.passes.push(Pass::StripDebug);
+        profile.enabled_features.insert(Feature::BulkMemory);
+
+        let mut profiles = ProfileRegistry::new();
+        profiles.register("release-small", profile);
+
+        let mut opts = OptimizationOptions::new_opt_level_0();
+        opts.add_pass(Pass::Dce);
+        profiles
+            .get("release-small")
+            .unwrap()
+            .clone()
+            .apply_to_opts(&mut opts);
+
+        assert_eq!(opts.passopts.optimize_level, OptimizeLevel::Level2);
+        assert_eq!(opts.passopts.shrink_level, ShrinkLevel::Level2);
+        assert!(opts.passes.add_default_passes);
+        assert!(opts.features.enabled.contains(&Feature::BulkMemory));
+
+        let passes: Vec<_> = opts.passes.more_passes.iter().map(Pass::name).collect();
+        assert_eq!(passes, vec!["dce", "strip-debug"]);
     }
 }
@@ -0,0 +1,312 @@
+//! Renaming imports and exports.
+//!
+//! Hosts differ in the names they give their imports and expect of exports.
+//! A [`RenameConfig`] maps the names of a module to those of a host,
+//! with explicit names and [`Regex`] rules.
+//! Set with [`OptimizationOptions::rename`](crate::OptimizationOptions::rename),
+//! it is applied to the input module before optimizing,
+//! and the resulting mapping is returned by
+//! [`OptimizationOptions::run_with_reports`](crate::OptimizationOptions::run_with_reports).
+//!
+//! Rules are tried in the order they are added, and the first matching rule applies.
+//! Regex rules replace the first match of the pattern,
+//! and the replacement may refer to capture groups as `$1` or `$name`.
+//!
+//! ```no_run
+//! use wasm_opt::OptimizationOptions;
+//! use wasm_opt::rename::{Regex, RenameConfig};
+//!
+//! let mut rename = RenameConfig::new();
+//! rename
+//!     .rename_import_module("env", "host")
+//!     .rename_export("_start", "main")
+//!     .rename_exports_matching(Regex::new("^wasm_(.*)$")?, "$1");
+//!
+//! let reports = OptimizationOptions::new_optimize_for_size()
+//!     .rename(rename)
+//!     .run_with_reports("input.wasm", "output.wasm")?;
+//!
+//! for export in &reports.rename.unwrap_or_default().exports {
+//!     println!("{} -> {}", export.old, export.new);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use crate::base::Module;
+use crate::contract::ExternalKind;
+use std::collections::HashSet;
+use thiserror::Error;
+
+pub use regex::Regex;
+
+/// Rules for renaming imports and exports.
+///
+/// Set with [`OptimizationOptions::rename`](crate::OptimizationOptions::rename).
+#[derive(Clone, Debug, Default)]
+pub struct RenameConfig {
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    target: Target,
+    pattern: Pattern,
+    replacement: String,
+}
+
+#[derive(Clone, Debug)]
+enum Target {
+    ImportModule,
+    ImportBase { module: Option<String> },
+    Export,
+}
+
+#[derive(Clone, Debug)]
+enum Pattern {
+    Exact(String),
+    Regex(Regex),
+}
+
+/// An error resulting from applying a [`RenameConfig`].
+#[derive(Error, Debug)]
+pub enum RenameError {
+    /// Two exports would have the same name.
+    #[error("Renaming results in duplicate export: {name}")]
+    DuplicateExport { name: String },
+    /// Binaryen failed to rename an import or export.
+    #[error("Failed to rename")]
+    Rename {
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+}
+
+/// The imports and exports renamed by a [`RenameConfig`],
+/// returned in [`RunReports::rename`](crate::RunReports::rename).
+#[derive(Clone, Debug, Default)]
+pub struct RenameReport {
+    /// The renamed imports, in module order.
+    pub imports: Vec<ImportRename>,
+    /// The renamed exports, in module order.
+    pub exports: Vec<ExportRename>,
+}
+
+/// A renamed import.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImportRename {
+    /// The kind of the import.
+    pub kind: ExternalKind,
+    /// The import module before renaming.
+    pub old_module: String,
+    /// The import name before renaming.
+    pub old_base: String,
+    /// The import module after renaming.
+    pub new_module: String,
+    /// The import name after renaming.
+    pub new_base: String,
+}
+
+/// A renamed export.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExportRename {
+    /// The export name before renaming.
+    pub old: String,
+    /// The export name after renaming.
+    pub new: String,
+}
+
+impl RenameConfig {
+    /// Create a configuration that renames nothing.
+    pub fn new() -> RenameConfig {
+        RenameConfig::default()
+    }
+
+    /// Rename an import module, for all of its imports.
+    pub fn rename_import_module(&mut self, old: &str, new: &str) -> &mut Self {
+        self.add(Target::ImportModule, Pattern::Exact(old.to_string()), new)
+    }
+
+    /// Rename an import module's import.
+    ///
+    /// `module` is the import module before renaming.
+    pub fn rename_import(&mut self, module: &str, old: &str, new: &str) -> &mut Self {
+        self.add(
+            Target::ImportBase {
+                module: Some(module.to_string()),
+            },
+            Pattern::Exact(old.to_string()),
+            new,
+        )
+    }
+
+    /// Rename an export.
+    pub fn rename_export(&mut self, old: &str, new: &str) -> &mut Self {
+        self.add(Target::Export, Pattern::Exact(old.to_string()), new)
+    }
+
+    /// Rename import modules matching a pattern.
+    pub fn rename_import_modules_matching(
+        &mut self,
+        pattern: Regex,
+        replacement: &str,
+    ) -> &mut Self {
+        self.add(Target::ImportModule, Pattern::Regex(pattern), replacement)
+    }
+
+    /// Rename imports of any module with names matching a pattern.
+    pub fn rename_imports_matching(&mut self, pattern: Regex, replacement: &str) -> &mut Self {
+        self.add(
+            Target::ImportBase { module: None },
+            Pattern::Regex(pattern),
+            replacement,
+        )
+    }
+
+    /// Rename exports matching a pattern.
+    pub fn rename_exports_matching(&mut self, pattern: Regex, replacement: &str) -> &mut Self {
+        self.add(Target::Export, Pattern::Regex(pattern), replacement)
+    }
+
+    fn add(&mut self, target: Target, pattern: Pattern, replacement: &str) -> &mut Self {
+        self.rules.push(Rule {
+            target,
+            pattern,
+            replacement: replacement.to_string(),
+        });
+        self
+    }
+
+    /// The new name for `name`, if a rule renames it.
+    fn map(&self, name: &str, applies: impl Fn(&Target) -> bool) -> Option<String> {
+        self.rules
+            .iter()
+            .filter(|rule| applies(&rule.target))
+            .find_map(|rule| rule.pattern.apply(name, &rule.replacement))
+    }
+
+    /// Rename the imports and exports of the module.
+    pub(crate) fn apply(&self, m: &mut Module) -> Result<RenameReport, RenameError> {
+        let mut report = RenameReport::default();
+
+        for (index, import) in m.imports().into_iter().enumerate() {
+            let new_module = self
+                .map(&import.module, |t| matches!(t, Target::ImportModule))
+                .unwrap_or_else(|| import.module.clone());
+            let new_base = self
+                .map(&import.base, |t| t.is_import_base_of(&import.module))
+                .unwrap_or_else(|| import.base.clone());
+
+            if new_module != import.module || new_base != import.base {
+                m.set_import_name(index as u32, &new_module, &new_base)
+                    .map_err(|e| RenameError::Rename {
+                        source: Box::from(e),
+                    })?;
+
+                report.imports.push(ImportRename {
+                    kind: ExternalKind::from_base(import.kind),
+                    old_module: import.module,
+                    old_base: import.base,
+                    new_module,
+                    new_base,
+                });
+            }
+        }
+
+        let exports = m.exports();
+        let mut names = HashSet::new();
+        let mut renames = vec![];
+
+        for (index, export) in exports.into_iter().enumerate() {
+            let new = self
+                .map(&export.name, |t| matches!(t, Target::Export))
+                .unwrap_or_else(|| export.name.clone());
+
+            if !names.insert(new.clone()) {
+                return Err(RenameError::DuplicateExport { name: new });
+            }
+
+            if new != export.name {
+                renames.push((
+                    index,
+                    ExportRename {
+                        old: export.name,
+                        new,
+                    },
+                ));
+            }
+        }
+
+        for (index, rename) in &renames {
+            m.set_export_name(*index as u32, &rename.new)
+                .map_err(|e| RenameError::Rename {
+                    source: Box::from(e),
+                })?;
+        }
+        m.update_maps();
+
+        report.exports = renames.into_iter().map(|(_, rename)| rename).collect();
+
+        Ok(report)
+    }
+}
+
+impl Target {
+    fn is_import_base_of(&self, module: &str) -> bool {
+        match self {
+            Target::ImportBase { module: Some(m) } => m == module,
+            Target::ImportBase { module: None } => true,
+            _ => false,
+        }
+    }
+}
+
+impl Pattern {
+    fn apply(&self, name: &str, replacement: &str) -> Option<String> {
+        match self {
+            Pattern::Exact(old) => {
+                if old == name {
+                    Some(replacement.to_string())
+                } else {
+                    None
+                }
+            }
+            Pattern::Regex(regex) => {
+                if regex.is_match(name) {
+                    Some(regex.replace(name, replacement).into_owned())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rule_order() {
+        let mut config = RenameConfig::new();
+        config
+            .rename_export("_start", "main")
+            .rename_exports_matching(Regex::new("^_(.*)$").unwrap(), "$1");
+
+        let export = |name: &str| config.map(name, |t| matches!(t, Target::Export));
+
+        assert_eq!(export("_start"), Some("main".to_string()));
+        assert_eq!(export("_init"), Some("init".to_string()));
+        assert_eq!(export("init"), None);
+    }
+
+    #[test]
+    fn test_import_base_module() {
+        let mut config = RenameConfig::new();
+        config.rename_import("env", "log", "print");
+
+        let base = |module: &str, name: &str| config.map(name, |t| t.is_import_base_of(module));
+
+        assert_eq!(base("env", "log"), Some("print".to_string()));
+        assert_eq!(base("wasi", "log"), None);
+    }
+}
@@ -1,9 +1,21 @@
 use crate::api::*;
+use crate::asyncify::{AsyncifyError, AsyncifyReport};
 use crate::base::{
     validate_wasm, Feature as BaseFeature, FeatureSet as BaseFeatureSet,
     InliningOptions as BaseInliningOptions, Module, ModuleReader, ModuleWriter,
     PassOptions as BasePassOptions, PassRunner,
 };
+use crate::check::OptionsIssue;
+use crate::contract::{ContractStage, ContractViolation};
+use crate::determinism::{DeterminismReport, DeterminismViolation};
+use crate::lower::UnsupportedFeature;
+use crate::merge::MergedModule;
+use crate::metadce::{MetaDceError, MetaDceReport};
+use crate::pass_args::PassArgs;
+use crate::passes::UnknownPassError;
+use crate::rename::{RenameError, RenameReport};
+use crate::stubs::StubError;
+use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 use thiserror::Error;
@@ -33,6 +45,99 @@ pub enum OptimizationError {
     /// but the API does not support reading stdin.
     #[error("Refusing to read from stdin")]
     InvalidStdinPath,
+    /// [`OptimizationOptions::check`] found errors,
+    /// and [`OptimizationOptions::reject_invalid_options`] is set.
+    #[error("Invalid optimization options")]
+    InvalidOptions { errors: Vec<OptionsIssue> },
+    /// A [`Pass::Custom`] pass is not registered with Binaryen.
+    ///
+    /// This is returned whether or not
+    /// [`OptimizationOptions::reject_invalid_options`] is set,
+    /// as Binaryen aborts on unknown passes.
+    #[error("Unknown pass")]
+    UnknownPass {
+        #[source]
+        source: UnknownPassError,
+    },
+    /// The module uses features that are not enabled,
+    /// and [`OptimizationOptions::lower_unsupported_features`]
+    /// could not lower them.
+    #[error("Module uses unsupported features: {}", feature_list(unsupported))]
+    UnsupportedFeatures {
+        unsupported: Vec<UnsupportedFeature>,
+    },
+    /// The optimized module has non-deterministic instructions,
+    /// and [`OptimizationOptions::determinism`] rejects them.
+    #[error("Module has {} non-determinism violations", violations.len())]
+    Nondeterministic {
+        violations: Vec<DeterminismViolation>,
+    },
+    /// The input or optimized module doesn't satisfy
+    /// [`OptimizationOptions::contract`].
+    #[error(
+        "Module contract violated ({stage:?} module): {}",
+        violation_list(violations)
+    )]
+    ContractViolated {
+        stage: ContractStage,
+        violations: Vec<ContractViolation>,
+    },
+    /// [`OptimizationOptions::stub_imports`] does not match the input module.
+    #[error("Failed to stub imports")]
+    StubImports {
+        #[source]
+        source: StubError,
+    },
+    /// [`OptimizationOptions::rename`] could not be applied.
+    #[error("Failed to rename imports and exports")]
+    Rename {
+        #[source]
+        source: RenameError,
+    },
+    /// [`OptimizationOptions::export_allowlist`] names exports
+    /// that are not in the input module.
+    #[error("Export allowlist names unknown exports: {}", names.join(", "))]
+    UnknownAllowlistExports { names: Vec<String> },
+    /// [`OptimizationOptions::metadce`] does not match the input module.
+    #[error("Invalid metadce graph")]
+    MetaDce {
+        #[source]
+        source: MetaDceError,
+    },
+    /// [`OptimizationOptions::asyncify`] does not match the input module.
+    #[error("Invalid Asyncify configuration")]
+    Asyncify {
+        #[source]
+        source: AsyncifyError,
+    },
+}
+
+/// The reports produced by a run,
+/// returned by [`OptimizationOptions::run_with_reports`].
+///
+/// Each report is `None` if the option producing it is not set.
+#[derive(Clone, Debug, Default)]
+pub struct RunReports {
+    /// The functions instrumented by [`OptimizationOptions::asyncify`].
+    pub asyncify: Option<AsyncifyReport>,
+    /// The non-deterministic instructions found by [`OptimizationOptions::determinism`]
+    /// in the optimized module.
+    pub determinism: Option<DeterminismReport>,
+    /// The imports and exports renamed by [`OptimizationOptions::rename`].
+    pub rename: Option<RenameReport>,
+    /// The items removed by [`OptimizationOptions::metadce`].
+    pub metadce: Option<MetaDceReport>,
+    /// The exports removed by [`OptimizationOptions::export_allowlist`],
+    /// in module order.
+    pub removed_exports: Option<Vec<String>>,
+}
+
+/// The module a run optimizes.
+enum RunInput<'a> {
+    /// A module file, with an optional sourcemap.
+    File(&'a Path, Option<&'a Path>),
+    /// A module already in memory.
+    Module(Module),
 }
 
 /// Execution.
@@ -85,6 +190,46 @@ impl OptimizationOptions {
     /// The Rust API does not support reading a module on stdin, as the CLI
     /// does. If `infile` is empty or "-",
     /// [`OptimizationError::InvalidStdinPath`] is returned.
+    ///
+    /// If [`OptimizationOptions::reject_invalid_options`] is true,
+    /// it returns [`OptimizationError::InvalidOptions`] if
+    /// [`OptimizationOptions::check`] finds errors.
+    ///
+    /// It returns [`OptimizationError::UnknownPass`] if a
+    /// [`Pass::Custom`] pass is not registered with Binaryen.
+    ///
+    /// If [`OptimizationOptions::asyncify`] is set, it returns
+    /// [`OptimizationError::Asyncify`] if the configuration
+    /// names imports or functions that are not in the input module.
+    ///
+    /// If [`OptimizationOptions::determinism`] is set and rejects violations,
+    /// it returns [`OptimizationError::Nondeterministic`] if the optimized
+    /// module has non-deterministic instructions.
+    ///
+    /// If [`OptimizationOptions::stub_imports`] is set, it returns
+    /// [`OptimizationError::StubImports`] if a stubbed import is not
+    /// in the input module, or its stub doesn't match its result type.
+    ///
+    /// If [`OptimizationOptions::rename`] is set, it returns
+    /// [`OptimizationError::Rename`] if renaming would result in
+    /// duplicate export names.
+    ///
+    /// If [`OptimizationOptions::contract`] is set, it returns
+    /// [`OptimizationError::ContractViolated`] if the input module
+    /// or the optimized module doesn't satisfy the contract.
+    ///
+    /// Exports not in [`OptimizationOptions::export_allowlist`], if set,
+    /// are removed before optimizing.
+    /// It returns [`OptimizationError::UnknownAllowlistExports`]
+    /// if the allowlist names exports that are not in the module.
+    ///
+    /// If [`OptimizationOptions::metadce`] is set, it returns
+    /// [`OptimizationError::MetaDce`] if the graph is inconsistent
+    /// or names exports or imports that are not in the module.
+    ///
+    /// If [`OptimizationOptions::lower_unsupported_features`] is true,
+    /// it returns [`OptimizationError::UnsupportedFeatures`] if
+    /// the module uses features that are not enabled and can't be lowered.
     pub fn run_with_sourcemaps(
         &self,
         infile: impl AsRef<Path>,
@@ -99,28 +244,123 @@ impl OptimizationOptions {
         let outfile_sourcemap: Option<&Path> = outfile_sourcemap.as_ref().map(AsRef::as_ref);
         let sourcemap_url: Option<&str> = sourcemap_url.as_ref().map(AsRef::as_ref);
 
-        if infile.as_os_str().is_empty() || infile == Path::new("-") {
-            return Err(OptimizationError::InvalidStdinPath);
+        self.run_module(
+            RunInput::File(infile, infile_sourcemap),
+            outfile,
+            outfile_sourcemap,
+            sourcemap_url,
+        )?;
+
+        Ok(())
+    }
+
+    /// Run the Binaryen wasm optimizer,
+    /// returning the reports of the options that produce them.
+    ///
+    /// # Errors
+    ///
+    /// As with [`OptimizationOptions::run_with_sourcemaps`].
+    pub fn run_with_reports(
+        &self,
+        infile: impl AsRef<Path>,
+        outfile: impl AsRef<Path>,
+    ) -> Result<RunReports, OptimizationError> {
+        self.run_module(
+            RunInput::File(infile.as_ref(), None),
+            outfile.as_ref(),
+            None,
+            None,
+        )
+    }
+
+    /// Run the Binaryen wasm optimizer on a module merged by
+    /// [`merge`](crate::merge::merge), writing it to a file
+    /// and returning the reports as [`OptimizationOptions::run_with_reports`] does.
+    ///
+    /// The merged module's features are set from
+    /// [`OptimizationOptions::features`].
+    ///
+    /// # Errors
+    ///
+    /// As with [`OptimizationOptions::run_with_sourcemaps`],
+    /// except that there is no input file to read.
+    pub fn run_merged(
+        &self,
+        merged: MergedModule,
+        outfile: impl AsRef<Path>,
+    ) -> Result<RunReports, OptimizationError> {
+        self.run_module(
+            RunInput::Module(merged.module),
+            outfile.as_ref(),
+            None,
+            None,
+        )
+    }
+
+    fn run_module(
+        &self,
+        input: RunInput,
+        outfile: &Path,
+        outfile_sourcemap: Option<&Path>,
+        sourcemap_url: Option<&str>,
+    ) -> Result<RunReports, OptimizationError> {
+        if let RunInput::File(infile, _) = input {
+            if infile.as_os_str().is_empty() || infile == Path::new("-") {
+                return Err(OptimizationError::InvalidStdinPath);
+            }
+        }
+
+        if let Some(pass) = self.individual_passes().iter().find(|p| !p.is_registered()) {
+            return Err(OptimizationError::UnknownPass {
+                source: UnknownPassError {
+                    name: pass.name().to_string(),
+                },
+            });
         }
 
-        let mut m = Module::new();
+        if self.reject_invalid_options {
+            let check = self.check();
+            if check.has_errors() {
+                return Err(OptimizationError::InvalidOptions {
+                    errors: check.errors,
+                });
+            }
+        }
+
+        let (mut m, read_from) = match input {
+            RunInput::File(infile, infile_sourcemap) => {
+                (Module::new(), Some((infile, infile_sourcemap)))
+            }
+            RunInput::Module(mut m) => {
+                // Start from the features of a module about to be read,
+                // replacing those it was built with.
+                m.set_features(Module::new().features());
+                (m, None)
+            }
+        };
         self.apply_features(&mut m);
 
-        {
-            let mut reader = ModuleReader::new();
+        let detect = matches!(self.features.baseline, FeatureBaseline::Detect);
+        let lowering_target = if self.lower_unsupported_features && !detect {
+            let target = m.features();
+            m.set_features(BaseFeature::All as u32);
+            Some(target)
+        } else {
+            None
+        };
 
-            let set_dwarf =
-                self.passopts.debug_info && !will_remove_debug_info(&self.passes.more_passes);
-            reader.set_dwarf(set_dwarf);
+        let mut reports = RunReports::default();
 
-            match self.reader.file_type {
-                FileType::Wasm => reader.read_binary(infile, &mut m, infile_sourcemap),
-                FileType::Wat => reader.read_text(infile, &mut m),
-                FileType::Any => reader.read(infile, &mut m, infile_sourcemap),
-            }
-            .map_err(|e| OptimizationError::Read {
-                source: Box::from(e),
-            })?;
+        if let Some((infile, infile_sourcemap)) = read_from {
+            self.read_module(infile, infile_sourcemap, &mut m)?;
+        }
+
+        if detect {
+            self.apply_detected_features(&mut m)?;
+        }
+
+        if let Some(target) = lowering_target {
+            self.lower_features(&mut m, target)?;
         }
 
         {
@@ -128,6 +368,65 @@ impl OptimizationOptions {
                 return Err(OptimizationError::ValidateWasmInput);
             }
 
+            self.apply_lowering_options(&mut m);
+
+            if let Some(stubs) = &self.stub_imports {
+                stubs
+                    .apply(&mut m)
+                    .map_err(|e| OptimizationError::StubImports { source: e })?;
+            }
+
+            if let Some(rename) = &self.rename {
+                let report = rename
+                    .apply(&mut m)
+                    .map_err(|e| OptimizationError::Rename { source: e })?;
+                reports.rename = Some(report);
+            }
+
+            self.check_contract(&m, ContractStage::Input)?;
+
+            let mut pruned_exports = Vec::new();
+            if let Some(allowlist) = &self.export_allowlist {
+                pruned_exports = prune_exports(&mut m, allowlist)?;
+                reports.removed_exports = Some(pruned_exports.clone());
+            }
+
+            // The graph may name exports the allowlist removed.
+            // Their nodes are reached as usual, but keep nothing.
+            if let Some(graph) = &self.metadce {
+                let report = graph
+                    .apply(&mut m, &pruned_exports, || self.translate_pass_options())
+                    .map_err(|e| OptimizationError::MetaDce { source: e })?;
+                reports.metadce = Some(report);
+            }
+
+            // As with `wasm-opt --asyncify -O`,
+            // the optimization passes clean up after Asyncify.
+            if let Some(asyncify) = &self.asyncify {
+                asyncify
+                    .validate(&m)
+                    .map_err(|e| OptimizationError::Asyncify { source: e })?;
+
+                let input_functions = m.function_names();
+
+                let mut passopts = self.translate_pass_options();
+                asyncify
+                    .to_arguments()
+                    .map_err(|e| OptimizationError::Asyncify {
+                        source: AsyncifyError::from(e),
+                    })?
+                    .iter()
+                    .for_each(|(key, value)| passopts.set_arguments(key, value));
+
+                {
+                    let mut pass_runner = PassRunner::new_with_options(&mut m, passopts);
+                    pass_runner.add(Pass::Asyncify.name());
+                    pass_runner.run();
+                }
+
+                reports.asyncify = Some(AsyncifyReport::from_module(&m, &input_functions));
+            }
+
             self.create_and_run_pass_runner(&mut m);
 
             if self.converge {
@@ -137,9 +436,45 @@ impl OptimizationOptions {
                     })?;
             }
 
+            if let Some(determinism) = &self.determinism {
+                let report = determinism.apply(&mut m, self.translate_pass_options());
+
+                if determinism.rejects() && !report.is_deterministic() {
+                    return Err(OptimizationError::Nondeterministic {
+                        violations: report.violations,
+                    });
+                }
+
+                reports.determinism = Some(report);
+            }
+
+            if self.writer.emit_target_features {
+                let passopts = self.translate_pass_options();
+                let mut pass_runner = PassRunner::new_with_options(&mut m, passopts);
+                pass_runner.add(Pass::EmitTargetFeatures.name());
+                pass_runner.run();
+            }
+
+            // Stack IR is discarded by any pass that modifies Binaryen IR,
+            // so these must run last.
+            if self.stack_ir.generate {
+                let passopts = self.translate_pass_options();
+                let mut pass_runner = PassRunner::new_with_options(&mut m, passopts);
+                pass_runner.add(Pass::GenerateStackIr.name());
+                if self.stack_ir.optimize {
+                    pass_runner.add(Pass::OptimizeStackIr.name());
+                }
+                if self.stack_ir.print {
+                    pass_runner.add(Pass::PrintStackIr.name());
+                }
+                pass_runner.run();
+            }
+
             if self.passopts.validate && !validate_wasm(&mut m) {
                 return Err(OptimizationError::ValidateWasmOutput);
             }
+
+            self.check_contract(&m, ContractStage::Output)?;
         }
 
         {
@@ -171,24 +506,154 @@ impl OptimizationOptions {
             })?;
         }
 
-        Ok(())
+        Ok(reports)
+    }
+
+    pub(crate) fn read_module(
+        &self,
+        infile: &Path,
+        infile_sourcemap: Option<&Path>,
+        m: &mut Module,
+    ) -> Result<(), OptimizationError> {
+        let mut reader = ModuleReader::new();
+
+        let set_dwarf =
+            self.passopts.debug_info && !will_remove_debug_info(&self.individual_passes());
+        reader.set_dwarf(set_dwarf);
+
+        match self.reader.file_type {
+            FileType::Wasm => reader.read_binary(infile, m, infile_sourcemap),
+            FileType::Wat => reader.read_text(infile, m),
+            FileType::Any => reader.read(infile, m, infile_sourcemap),
+        }
+        .map_err(|e| OptimizationError::Read {
+            source: Box::from(e),
+        })
     }
 
     fn create_and_run_pass_runner(&self, m: &mut Module) {
-        let passopts = self.translate_pass_options();
+        self.build_pass_runners(m, &mut |pass_runner, _, _| pass_runner.run());
+    }
 
-        let mut pass_runner = PassRunner::new_with_options(m, passopts);
+    /// Build the pass runners for the passes or pipeline,
+    /// calling `f` on each in order, along with its levels.
+    ///
+    /// As with `wasm-opt`, each run of the default passes or a default phase
+    /// in a pipeline gets its own pass runner,
+    /// and consecutive individual passes share a pass runner.
+    ///
+    /// Unregistered [`Pass::Custom`] passes are skipped,
+    /// since Binaryen aborts on them.
+    pub(crate) fn build_pass_runners(
+        &self,
+        m: &mut Module,
+        f: &mut dyn FnMut(&mut PassRunner, OptimizeLevel, ShrinkLevel),
+    ) {
+        let pipeline = if let Some(pipeline) = &self.pipeline {
+            pipeline
+        } else {
+            let passopts = self.translate_pass_options();
+
+            let mut pass_runner = PassRunner::new_with_options(m, passopts);
+
+            if self.passes.add_default_passes {
+                pass_runner.add_default_optimization_passes();
+            }
+
+            self.passes
+                .more_passes
+                .iter()
+                .filter(|pass| pass.is_registered())
+                .for_each(|pass| pass_runner.add(pass.name()));
+
+            f(
+                &mut pass_runner,
+                self.passopts.optimize_level,
+                self.passopts.shrink_level,
+            );
+            return;
+        };
+
+        let mut passes = vec![];
+        let mut levels = (self.passopts.optimize_level, self.passopts.shrink_level);
+
+        for stage in &pipeline.stages {
+            let (phase, optimize_level, shrink_level) = match stage {
+                Stage::Pass(pass) => {
+                    passes.push(pass.clone());
+                    continue;
+                }
+                Stage::Levels {
+                    optimize_level,
+                    shrink_level,
+                } => {
+                    self.build_individual_pass_runner(m, &passes, levels, f);
+                    passes.clear();
+                    levels = (*optimize_level, *shrink_level);
+                    continue;
+                }
+                Stage::DefaultPasses {
+                    optimize_level,
+                    shrink_level,
+                } => (None, *optimize_level, *shrink_level),
+                Stage::DefaultPhase {
+                    phase,
+                    optimize_level,
+                    shrink_level,
+                } => (Some(*phase), *optimize_level, *shrink_level),
+            };
+
+            self.build_individual_pass_runner(m, &passes, levels, f);
+            passes.clear();
+            levels = (optimize_level, shrink_level);
+
+            let mut passopts = self.translate_pass_options();
+            passopts.set_optimize_level(optimize_level as i32);
+            passopts.set_shrink_level(shrink_level as i32);
+
+            let mut pass_runner = PassRunner::new_with_options(m, passopts);
+            match phase {
+                None => pass_runner.add_default_optimization_passes(),
+                Some(DefaultPhase::GlobalPre) => {
+                    pass_runner.add_default_global_optimization_pre_passes()
+                }
+                Some(DefaultPhase::Function) => {
+                    pass_runner.add_default_function_optimization_passes()
+                }
+                Some(DefaultPhase::GlobalPost) => {
+                    pass_runner.add_default_global_optimization_post_passes()
+                }
+            }
+            f(&mut pass_runner, optimize_level, shrink_level);
+        }
+
+        self.build_individual_pass_runner(m, &passes, levels, f);
+    }
 
-        if self.passes.add_default_passes {
-            pass_runner.add_default_optimization_passes();
+    /// Build a pass runner for consecutive individual passes of a pipeline,
+    /// running with the levels of the stage before them.
+    fn build_individual_pass_runner(
+        &self,
+        m: &mut Module,
+        passes: &[Pass],
+        (optimize_level, shrink_level): (OptimizeLevel, ShrinkLevel),
+        f: &mut dyn FnMut(&mut PassRunner, OptimizeLevel, ShrinkLevel),
+    ) {
+        if passes.is_empty() {
+            return;
         }
 
-        self.passes
-            .more_passes
+        let mut passopts = self.translate_pass_options();
+        passopts.set_optimize_level(optimize_level as i32);
+        passopts.set_shrink_level(shrink_level as i32);
+
+        let mut pass_runner = PassRunner::new_with_options(m, passopts);
+        passes
             .iter()
+            .filter(|pass| pass.is_registered())
             .for_each(|pass| pass_runner.add(pass.name()));
 
-        pass_runner.run();
+        f(&mut pass_runner, optimize_level, shrink_level);
     }
 
     fn run_until_convergence(&self, m: &mut Module) -> anyhow::Result<()> {
@@ -222,23 +687,40 @@ impl OptimizationOptions {
         Ok(file_size)
     }
 
-    fn apply_features(&self, m: &mut Module) {
+    fn check_contract(&self, m: &Module, stage: ContractStage) -> Result<(), OptimizationError> {
+        if let Some(contract) = &self.contract {
+            let violations = contract.check(m);
+            if !violations.is_empty() {
+                return Err(OptimizationError::ContractViolated { stage, violations });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn apply_features(&self, m: &mut Module) {
         let (enabled_features, disabled_features) = convert_feature_sets(&self.features);
 
         m.apply_features(enabled_features, disabled_features);
     }
 
-    fn translate_pass_options(&self) -> BasePassOptions {
+    pub(crate) fn translate_pass_options(&self) -> BasePassOptions {
         let mut opts = BasePassOptions::new();
 
         opts.set_validate(self.passopts.validate);
         opts.set_validate_globally(self.passopts.validate_globally);
         opts.set_optimize_level(self.passopts.optimize_level as i32);
         opts.set_shrink_level(self.passopts.shrink_level as i32);
+        opts.set_ignore_implicit_traps(self.passopts.ignore_implicit_traps);
         opts.set_traps_never_happen(self.passopts.traps_never_happen);
         opts.set_low_memory_unused(self.passopts.low_memory_unused);
         opts.set_fast_math(self.passopts.fast_math);
         opts.set_zero_filled_memory(self.passopts.zero_filled_memory);
+        opts.set_closed_world(self.passopts.closed_world);
+        self.passopts
+            .passes_to_skip
+            .iter()
+            .for_each(|pass| opts.add_pass_to_skip(pass.name()));
         opts.set_debug_info(self.passopts.debug_info);
 
         self.passopts
@@ -259,10 +741,58 @@ impl OptimizationOptions {
     }
 }
 
-fn will_remove_debug_info(passes: &[Pass]) -> bool {
-    passes
+fn prune_exports(
+    m: &mut Module,
+    allowlist: &HashSet<String>,
+) -> Result<Vec<String>, OptimizationError> {
+    let exports: Vec<String> = m.exports().into_iter().map(|export| export.name).collect();
+
+    let mut unknown: Vec<String> = allowlist
+        .iter()
+        .filter(|name| !exports.contains(name))
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        unknown.sort();
+        return Err(OptimizationError::UnknownAllowlistExports { names: unknown });
+    }
+
+    let removed: Vec<String> = exports
+        .into_iter()
+        .filter(|name| !allowlist.contains(name))
+        .collect();
+
+    for name in &removed {
+        m.remove_export(name);
+    }
+
+    Ok(removed)
+}
+
+fn violation_list(violations: &[ContractViolation]) -> String {
+    violations
         .iter()
-        .any(|pass| PassRunner::pass_removes_debug_info(pass.name()) == true)
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn feature_list(unsupported: &[UnsupportedFeature]) -> String {
+    unsupported
+        .iter()
+        .map(|u| {
+            if u.functions.is_empty() {
+                u.feature.to_string()
+            } else {
+                format!("{} (in {})", u.feature, u.functions.join(", "))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn will_remove_debug_info(passes: &[Pass]) -> bool {
+    passes.iter().any(|pass| pass.removes_debug_info())
 }
 
 fn convert_feature_sets(features: &Features) -> (BaseFeatureSet, BaseFeatureSet) {
@@ -277,20 +807,21 @@ fn convert_feature_sets(features: &Features) -> (BaseFeatureSet, BaseFeatureSet)
             feature_set_enabled.set_mvp();
             feature_set_disabled.set_all();
         }
-        FeatureBaseline::All => {
+        // Detection needs to read the module with all features.
+        FeatureBaseline::All | FeatureBaseline::Detect => {
             feature_set_enabled.set_all();
             feature_set_disabled.set_mvp();
         }
     }
 
     features.enabled.iter().for_each(|f| {
-        let feature = convert_feature(f);
+        let feature = f.to_base();
         feature_set_enabled.set(feature, true);
         feature_set_disabled.set(feature, false);
     });
 
     features.disabled.iter().for_each(|f| {
-        let feature = convert_feature(f);
+        let feature = f.to_base();
         feature_set_enabled.set(feature, false);
         feature_set_disabled.set(feature, true);
     });
@@ -298,31 +829,6 @@ fn convert_feature_sets(features: &Features) -> (BaseFeatureSet, BaseFeatureSet)
     (feature_set_enabled, feature_set_disabled)
 }
 
-fn convert_feature(feature: &Feature) -> BaseFeature {
-    match feature {
-        Feature::None => BaseFeature::None,
-        Feature::Atomics => BaseFeature::Atomics,
-        Feature::MutableGlobals => BaseFeature::MutableGlobals,
-        Feature::TruncSat => BaseFeature::TruncSat,
-        Feature::Simd => BaseFeature::Simd,
-        Feature::BulkMemory => BaseFeature::BulkMemory,
-        Feature::SignExt => BaseFeature::SignExt,
-        Feature::ExceptionHandling => BaseFeature::ExceptionHandling,
-        Feature::TailCall => BaseFeature::TailCall,
-        Feature::ReferenceTypes => BaseFeature::ReferenceTypes,
-        Feature::Multivalue => BaseFeature::Multivalue,
-        Feature::Gc => BaseFeature::Gc,
-        Feature::Memory64 => BaseFeature::Memory64,
-        Feature::RelaxedSimd => BaseFeature::RelaxedSimd,
-        Feature::ExtendedConst => BaseFeature::ExtendedConst,
-        Feature::Strings => BaseFeature::Strings,
-        Feature::MultiMemory => BaseFeature::MultiMemory,
-        Feature::Mvp => BaseFeature::None,
-        Feature::Default => BaseFeature::Default,
-        Feature::All => BaseFeature::All,
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
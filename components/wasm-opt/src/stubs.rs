@@ -0,0 +1,197 @@
+//! Replacing imported functions with stubs.
+//!
+//! Running a module in a test harness often requires only part of its host.
+//! [`ImportStubs`] replaces chosen imported functions with internal functions
+//! that trap, return zero, or return a constant,
+//! removing the imports.
+//! This is a configurable alternative to the [`Pass::RemoveImports`](crate::Pass::RemoveImports)
+//! and [`Pass::StubUnsupportedJs`](crate::Pass::StubUnsupportedJs) passes.
+//!
+//! ```no_run
+//! use wasm_opt::OptimizationOptions;
+//! use wasm_opt::stubs::{ImportStubs, StubValue};
+//!
+//! let mut stubs = ImportStubs::new();
+//! stubs
+//!     .trap("env", "abort")
+//!     .zero("env", "log")
+//!     .constant("env", "get_time", StubValue::I64(0));
+//!
+//! OptimizationOptions::new_optimize_for_size()
+//!     .stub_imports(stubs)
+//!     .run("input.wasm", "output.wasm")?;
+//! # Ok::<(), wasm_opt::OptimizationError>(())
+//! ```
+
+use crate::base::{Module, StubConstType, StubKind, StubResult};
+use crate::contract::ExternalKind;
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Imported functions to replace with stubs.
+///
+/// Set with [`OptimizationOptions::stub_imports`](crate::OptimizationOptions::stub_imports).
+///
+/// Import names may use `*` as the base to stub
+/// all imported functions of a module.
+/// Names are those of the input module,
+/// before [`OptimizationOptions::rename`](crate::OptimizationOptions::rename) is applied.
+#[derive(Clone, Debug, Default)]
+pub struct ImportStubs {
+    stubs: Vec<ImportStub>,
+}
+
+#[derive(Clone, Debug)]
+struct ImportStub {
+    module: String,
+    base: String,
+    stub: Stub,
+}
+
+/// The body of a stub function.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Stub {
+    /// Trap when called.
+    Trap,
+    /// Return zero, or null references, of the result types,
+    /// or return nothing.
+    Zero,
+    /// Return a constant, which must be of the result type.
+    Constant(StubValue),
+}
+
+/// A constant returned by [`Stub::Constant`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StubValue {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+/// An error resulting from [`ImportStubs`]
+/// that don't match the module.
+#[derive(Error, Debug)]
+pub enum StubError {
+    /// No imported function has the name.
+    #[error("Stubbed import not found in module: {module}.{base}")]
+    UnknownImport { module: String, base: String },
+    /// A constant doesn't have the type the function returns.
+    #[error("Stub constant doesn't match the result type of {module}.{base}")]
+    TypeMismatch { module: String, base: String },
+    /// The function returns a type without a zero value,
+    /// like a non-nullable reference.
+    #[error("Can't return zero from {module}.{base}")]
+    NoZeroValue { module: String, base: String },
+}
+
+impl ImportStubs {
+    /// Create a configuration that stubs no imports.
+    pub fn new() -> ImportStubs {
+        ImportStubs::default()
+    }
+
+    /// Replace an imported function with a stub.
+    pub fn stub(&mut self, module: &str, base: &str, stub: Stub) -> &mut Self {
+        self.stubs.push(ImportStub {
+            module: module.to_string(),
+            base: base.to_string(),
+            stub,
+        });
+        self
+    }
+
+    /// Replace an imported function with a function that traps.
+    pub fn trap(&mut self, module: &str, base: &str) -> &mut Self {
+        self.stub(module, base, Stub::Trap)
+    }
+
+    /// Replace an imported function with a function that returns zero.
+    pub fn zero(&mut self, module: &str, base: &str) -> &mut Self {
+        self.stub(module, base, Stub::Zero)
+    }
+
+    /// Replace an imported function with a function that returns a constant.
+    pub fn constant(&mut self, module: &str, base: &str, value: StubValue) -> &mut Self {
+        self.stub(module, base, Stub::Constant(value))
+    }
+
+    /// Stub the imports.
+    ///
+    /// If several stubs name an import the first one applies.
+    pub(crate) fn apply(&self, m: &mut Module) -> Result<(), StubError> {
+        let imports: Vec<(String, String)> = m
+            .imports()
+            .into_iter()
+            .filter(|import| ExternalKind::from_base(import.kind) == ExternalKind::Function)
+            .map(|import| (import.module, import.base))
+            .collect();
+
+        for stub in &self.stubs {
+            let found = imports
+                .iter()
+                .any(|(module, base)| stub.matches(module, base));
+            if !found {
+                return Err(StubError::UnknownImport {
+                    module: stub.module.clone(),
+                    base: stub.base.clone(),
+                });
+            }
+        }
+
+        // Each call stubs all imports with the name.
+        let mut stubbed = HashSet::new();
+
+        for (module, base) in &imports {
+            if !stubbed.insert((module, base)) {
+                continue;
+            }
+
+            let stub = match self.stubs.iter().find(|s| s.matches(module, base)) {
+                Some(stub) => stub.stub,
+                None => continue,
+            };
+
+            let (kind, const_type, const_bits) = match stub {
+                Stub::Trap => (StubKind::Trap, StubConstType::I32, 0),
+                Stub::Zero => (StubKind::Zero, StubConstType::I32, 0),
+                Stub::Constant(StubValue::I32(v)) => {
+                    (StubKind::Constant, StubConstType::I32, v as u32 as u64)
+                }
+                Stub::Constant(StubValue::I64(v)) => {
+                    (StubKind::Constant, StubConstType::I64, v as u64)
+                }
+                Stub::Constant(StubValue::F32(v)) => {
+                    (StubKind::Constant, StubConstType::F32, v.to_bits() as u64)
+                }
+                Stub::Constant(StubValue::F64(v)) => {
+                    (StubKind::Constant, StubConstType::F64, v.to_bits())
+                }
+            };
+
+            match m.stub_imported_function(module, base, kind, const_type, const_bits) {
+                StubResult::TypeMismatch => {
+                    return Err(StubError::TypeMismatch {
+                        module: module.clone(),
+                        base: base.clone(),
+                    })
+                }
+                StubResult::NoZeroValue => {
+                    return Err(StubError::NoZeroValue {
+                        module: module.clone(),
+                        base: base.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ImportStub {
+    fn matches(&self, module: &str, base: &str) -> bool {
+        self.module == module && (self.base == "*" || self.base == base)
+    }
+}
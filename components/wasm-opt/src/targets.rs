@@ -0,0 +1,127 @@
+//! Named feature sets for common runtimes.
+//!
+//! A [`Target`] maps a runtime to the wasm [`Feature`]s it supports,
+//! and is applied with [`OptimizationOptions::target`],
+//! the `target` key of the [`config`](crate::config) metadata,
+//! or the `--target <name>` argument to
+//! [`run_from_command_args`](crate::integration::run_from_command_args).
+//!
+//! ```
+//! use wasm_opt::targets::Target;
+//! use wasm_opt::{Feature, OptimizationOptions};
+//!
+//! let mut opts = OptimizationOptions::new_optimize_for_size();
+//! opts.target(Target::Browser).enable_feature(Feature::Simd);
+//! ```
+
+use crate::api::{Feature, FeatureBaseline, Features, OptimizationOptions};
+use strum_macros::{EnumIter, EnumString};
+
+/// A runtime with a known set of supported features.
+///
+/// These are conservative: a runtime may support more features,
+/// some only when configured to.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, EnumIter, EnumString)]
+pub enum Target {
+    /// Features supported by all major browsers,
+    /// as enabled by LLVM's `generic` wasm CPU.
+    #[strum(serialize = "browser")]
+    Browser,
+    /// Features enabled by default in Wasmtime.
+    #[strum(serialize = "wasmtime")]
+    Wasmtime,
+    /// Only the features of runtimes that require deterministic execution,
+    /// such as blockchain runtimes.
+    ///
+    /// Excludes threads and SIMD, along with all but the
+    /// simplest post-MVP features.
+    #[strum(serialize = "deterministic")]
+    Deterministic,
+}
+
+impl Target {
+    /// Returns the name of the target,
+    /// as accepted by `Target::from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Target::Browser => "browser",
+            Target::Wasmtime => "wasmtime",
+            Target::Deterministic => "deterministic",
+        }
+    }
+
+    /// The features the target supports, beyond the MVP.
+    pub fn features(&self) -> &'static [Feature] {
+        match self {
+            Target::Browser => &[
+                Feature::MutableGlobals,
+                Feature::SignExt,
+                Feature::TruncSat,
+                Feature::BulkMemory,
+                Feature::Multivalue,
+                Feature::ReferenceTypes,
+            ],
+            Target::Wasmtime => &[
+                Feature::MutableGlobals,
+                Feature::SignExt,
+                Feature::TruncSat,
+                Feature::BulkMemory,
+                Feature::Multivalue,
+                Feature::ReferenceTypes,
+                Feature::Simd,
+                Feature::RelaxedSimd,
+                Feature::TailCall,
+                Feature::MultiMemory,
+            ],
+            Target::Deterministic => &[Feature::MutableGlobals, Feature::SignExt],
+        }
+    }
+
+    /// The [`Features`] that enable exactly the target's features.
+    pub fn to_features(&self) -> Features {
+        Features {
+            baseline: FeatureBaseline::MvpOnly,
+            enabled: self.features().iter().copied().collect(),
+            disabled: Default::default(),
+        }
+    }
+}
+
+/// Targets.
+impl OptimizationOptions {
+    /// Replaces [`OptimizationOptions::features`] with the features of a target.
+    ///
+    /// Features enabled or disabled afterwards are applied on top of the target.
+    pub fn target(&mut self, target: Target) -> &mut Self {
+        self.features = target.to_features();
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_target_names() {
+        for target in Target::iter() {
+            assert_eq!(Target::from_str(target.name()).unwrap(), target);
+        }
+    }
+
+    #[test]
+    fn test_target_features() {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+        opts.enable_feature(Feature::Gc)
+            .target(Target::Deterministic)
+            .enable_feature(Feature::BulkMemory);
+
+        assert!(matches!(opts.features.baseline, FeatureBaseline::MvpOnly));
+        assert!(!opts.features.enabled.contains(&Feature::Gc));
+        assert!(opts.features.enabled.contains(&Feature::SignExt));
+        assert!(opts.features.enabled.contains(&Feature::BulkMemory));
+    }
+}
@@ -1,29 +1,54 @@
 use std::collections::HashSet;
 use strum::IntoEnumIterator;
+use wasm_opt::asyncify::{AsyncifyConfig, AsyncifyError};
 use wasm_opt::base::pass_registry;
 use wasm_opt::base::InliningOptions as BaseInliningOptions;
+use wasm_opt::base::ModuleReader;
 use wasm_opt::base::PassOptions as BasePassOptions;
 use wasm_opt::base::{
     check_inlining_options_defaults, check_pass_options_defaults, check_pass_options_defaults_os,
     pass_registry::is_pass_hidden,
 };
+use wasm_opt::contract::{ContractStage, ContractViolation, ModuleContract, ValType};
+use wasm_opt::determinism::{DeterminismConfig, ViolationKind};
+use wasm_opt::merge::{merge_files, ExportConflicts, MergeError, MergeOptions, Module};
+use wasm_opt::metadce::{MetaDceError, MetaDceGraph};
+use wasm_opt::rename::{ExportRename, Regex, RenameConfig, RenameError};
+use wasm_opt::stubs::{ImportStubs, StubError, StubValue};
 use wasm_opt::*;
 
 use std::error::Error;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::BufWriter;
 use std::io::Write;
-use tempfile::Builder;
+use std::path::PathBuf;
+use tempfile::{Builder, TempDir};
 
+static WASM_FILE: &[u8] = include_bytes!("hello_world.wasm");
 static GARBAGE_FILE: &[u8] = include_bytes!("garbage_file.wat");
 
+/// Writes a fixture to `infile.{extension}` in a temporary directory,
+/// returning the directory and the input and output paths in it.
+fn write_fixture(
+    extension: &str,
+    contents: impl AsRef<[u8]>,
+) -> anyhow::Result<(TempDir, PathBuf, PathBuf)> {
+    let temp_dir = Builder::new().prefix("wasm_opt_tests").tempdir()?;
+    let inpath = temp_dir.path().join(format!("infile.{}", extension));
+    let outpath = temp_dir.path().join("outfile.wasm");
+
+    fs::write(&inpath, contents)?;
+
+    Ok((temp_dir, inpath, outpath))
+}
+
 #[test]
 fn all_passes_correct() -> anyhow::Result<()> {
     let mut passes_via_base_rs = HashSet::<String>::new();
     pass_registry::get_registered_names()
         .iter()
         .for_each(|name| {
-            if !is_pass_hidden(name) {
+            if is_pass_hidden(name) == Some(false) {
                 passes_via_base_rs.insert(name.to_string());
             }
         });
@@ -43,6 +68,49 @@ fn all_passes_correct() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn custom_pass_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture("wasm", WASM_FILE)?;
+
+    let pass = Pass::custom("dce")?;
+    assert_eq!(pass.name(), "dce");
+
+    OptimizationOptions::new_opt_level_0()
+        .add_pass(pass)
+        .run(&inpath, &outpath)?;
+
+    assert!(matches!(
+        Pass::custom("not-a-pass"),
+        Err(UnknownPassError { .. })
+    ));
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .add_pass(Pass::Custom("not-a-pass".to_string()))
+        .run(&inpath, &outpath);
+    assert!(matches!(res, Err(OptimizationError::UnknownPass { .. })));
+
+    Ok(())
+}
+
+#[test]
+fn pass_metadata_works() -> anyhow::Result<()> {
+    assert_eq!("dce".parse::<Pass>()?, Pass::Dce);
+    assert!("not-a-pass".parse::<Pass>().is_err());
+
+    assert!(!Pass::Dce.is_hidden()?);
+    assert!(Pass::Custom("not-a-pass".to_string()).is_hidden().is_err());
+
+    assert!(!Pass::Dce.description()?.is_empty());
+    assert!(Pass::Custom("not-a-pass".to_string())
+        .description()
+        .is_err());
+
+    assert!(Pass::StripDebug.removes_debug_info());
+    assert!(!Pass::Dce.removes_debug_info());
+
+    Ok(())
+}
+
 #[test]
 fn test_inlining_options_defaults() -> anyhow::Result<()> {
     let inlining_defaults = InliningOptions::default();
@@ -66,10 +134,12 @@ fn test_pass_options_defaults() -> anyhow::Result<()> {
     pass_options.set_validate_globally(pass_options_defaults.validate_globally);
     pass_options.set_optimize_level(pass_options_defaults.optimize_level as i32);
     pass_options.set_shrink_level(pass_options_defaults.shrink_level as i32);
+    pass_options.set_ignore_implicit_traps(pass_options_defaults.ignore_implicit_traps);
     pass_options.set_traps_never_happen(pass_options_defaults.traps_never_happen);
     pass_options.set_low_memory_unused(pass_options_defaults.low_memory_unused);
     pass_options.set_fast_math(pass_options_defaults.fast_math);
     pass_options.set_zero_filled_memory(pass_options_defaults.zero_filled_memory);
+    pass_options.set_closed_world(pass_options_defaults.closed_world);
     pass_options.set_debug_info(pass_options_defaults.debug_info);
 
     assert_eq!(check_pass_options_defaults(pass_options), true);
@@ -86,10 +156,12 @@ fn test_optimization_options_os() -> anyhow::Result<()> {
     pass_options.set_validate_globally(opts.passopts.validate_globally);
     pass_options.set_optimize_level(opts.passopts.optimize_level as i32);
     pass_options.set_shrink_level(opts.passopts.shrink_level as i32);
+    pass_options.set_ignore_implicit_traps(opts.passopts.ignore_implicit_traps);
     pass_options.set_traps_never_happen(opts.passopts.traps_never_happen);
     pass_options.set_low_memory_unused(opts.passopts.low_memory_unused);
     pass_options.set_fast_math(opts.passopts.fast_math);
     pass_options.set_zero_filled_memory(opts.passopts.zero_filled_memory);
+    pass_options.set_closed_world(opts.passopts.closed_world);
     pass_options.set_debug_info(opts.passopts.debug_info);
 
     assert_eq!(check_pass_options_defaults_os(pass_options), true);
@@ -116,3 +188,897 @@ fn optimization_read_module_error_works() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn extra_pass_options_work() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture("wasm", WASM_FILE)?;
+
+    OptimizationOptions::new_optimize_for_size()
+        .ignore_implicit_traps(true)
+        .closed_world(true)
+        .skip_pass(Pass::Vacuum)
+        .emit_target_features(true)
+        .run(&inpath, &outpath)?;
+
+    let infile_reader = fs::read(&inpath)?;
+    let outfile_reader = fs::read(&outpath)?;
+
+    assert!(infile_reader.len() > outfile_reader.len());
+
+    Ok(())
+}
+
+#[test]
+fn stack_ir_options_work() -> anyhow::Result<()> {
+    let (temp_dir, inpath, outpath) = write_fixture("wasm", WASM_FILE)?;
+    let outpath_stack_ir = temp_dir.path().join("outfile_stack_ir.wasm");
+
+    OptimizationOptions::new_optimize_for_size().run(&inpath, &outpath)?;
+
+    OptimizationOptions::new_optimize_for_size()
+        .generate_stack_ir(true)
+        .optimize_stack_ir(true)
+        .run(&inpath, &outpath_stack_ir)?;
+
+    let outfile_reader = fs::read(&outpath)?;
+    let outfile_stack_ir_reader = fs::read(&outpath_stack_ir)?;
+
+    assert!(outfile_reader.len() >= outfile_stack_ir_reader.len());
+
+    Ok(())
+}
+
+#[test]
+fn pipeline_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture("wasm", WASM_FILE)?;
+
+    let pipeline: Pipeline = "dce,O2,inlining-optimizing,Oz".parse()?;
+
+    OptimizationOptions::new_opt_level_0()
+        .pipeline(pipeline)
+        .run(&inpath, &outpath)?;
+
+    let infile_reader = fs::read(&inpath)?;
+    let outfile_reader = fs::read(&outpath)?;
+
+    assert!(infile_reader.len() > outfile_reader.len());
+
+    Ok(())
+}
+
+#[test]
+fn pipeline_default_phases_work() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture("wasm", WASM_FILE)?;
+
+    let (o, s) = (OptimizeLevel::Level2, ShrinkLevel::Level1);
+    let mut pipeline = Pipeline::new();
+    pipeline
+        .add_default_phase(DefaultPhase::GlobalPre, o, s)
+        .add_pass(Pass::Asyncify)
+        .add_default_phase(DefaultPhase::Function, o, s)
+        .add_default_phase(DefaultPhase::GlobalPost, o, s);
+
+    OptimizationOptions::new_opt_level_0()
+        .pipeline(pipeline)
+        .run(&inpath, &outpath)?;
+
+    assert!(outpath.exists());
+
+    Ok(())
+}
+
+#[test]
+fn pipeline_levels_reach_passes() -> anyhow::Result<()> {
+    // `inlining` only inlines a small function with two callers
+    // at optimize level 3 and shrink level 0.
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (func $f (param i32) (result i32)
+               (i32.add (i32.mul (local.get 0) (i32.const 3)) (i32.const 7)))
+             (func (export "a") (result i32) (call $f (i32.const 1)))
+             (func (export "b") (result i32) (call $f (i32.const 2))))"#,
+    )?;
+
+    let inlines = |passopts_level: OptimizeLevel, pipeline: &Pipeline| -> anyhow::Result<bool> {
+        let mut opts = OptimizationOptions::new_opt_level_0();
+        opts.optimize_level(passopts_level)
+            .pipeline(pipeline.clone())
+            .writer_file_type(FileType::Wat)
+            .run(&inpath, &outpath)?;
+        Ok(!fs::read_to_string(&outpath)?.contains("call $f"))
+    };
+
+    let mut pipeline = Pipeline::new();
+    pipeline.add_pass(Pass::Inlining);
+    assert!(!inlines(OptimizeLevel::Level0, &pipeline)?);
+    assert!(inlines(OptimizeLevel::Level3, &pipeline)?);
+
+    let mut pipeline = Pipeline::new();
+    pipeline
+        .set_levels(OptimizeLevel::Level3, ShrinkLevel::Level0)
+        .add_pass(Pass::Inlining);
+    assert!(inlines(OptimizeLevel::Level0, &pipeline)?);
+
+    let pipeline: Pipeline = "O0,inlining".parse()?;
+    assert!(!inlines(OptimizeLevel::Level3, &pipeline)?);
+
+    Ok(())
+}
+
+#[test]
+fn planned_passes_works() -> anyhow::Result<()> {
+    let mut opts = OptimizationOptions::new_optimize_for_size();
+    opts.add_pass(Pass::Asyncify)
+        .set_pass_arg("asyncify-imports", "env.sleep");
+
+    let planned = opts.planned_passes();
+
+    assert!(planned.len() > 2);
+    assert!(planned
+        .iter()
+        .all(|pass| pass.optimize_level == OptimizeLevel::Level2
+            && pass.shrink_level == ShrinkLevel::Level1));
+
+    let last = planned.last().unwrap();
+    assert_eq!(last.name, "asyncify");
+    assert_eq!(
+        last.arguments,
+        vec![("asyncify-imports".to_string(), "env.sleep".to_string())]
+    );
+
+    let opts = OptimizationOptions::new_opt_level_0();
+    assert!(opts.planned_passes().is_empty());
+
+    let mut opts = OptimizationOptions::new_opt_level_0();
+    opts.pipeline("dce,O3,vacuum".parse()?);
+
+    let planned = opts.planned_passes();
+    assert_eq!(planned.first().unwrap().name, "dce");
+    assert_eq!(planned.last().unwrap().name, "vacuum");
+    assert!(planned[1..planned.len() - 1]
+        .iter()
+        .all(|pass| pass.optimize_level == OptimizeLevel::Level3));
+
+    let mut asyncify = AsyncifyConfig::new();
+    asyncify.import("env", "sleep");
+
+    let mut opts = OptimizationOptions::new_opt_level_0();
+    opts.asyncify(asyncify)
+        .add_pass(Pass::ExtractFunction)
+        .add_pass(Pass::Vacuum)
+        .skip_pass(Pass::Vacuum)
+        .set_pass_arg("extract-function", "main")
+        .set_pass_arg("extract-function-index", "0")
+        .generate_stack_ir(true);
+
+    let planned = opts.planned_passes();
+    let names: Vec<&str> = planned.iter().map(|pass| pass.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["asyncify", "extract-function", "generate-stack-ir"]
+    );
+    assert_eq!(
+        planned[0].arguments,
+        vec![("asyncify-imports".to_string(), "env.sleep".to_string())]
+    );
+    assert_eq!(
+        planned[1].arguments,
+        vec![("extract-function".to_string(), "main".to_string())]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn asyncify_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (import "env" "sleep" (func $sleep))
+             (import "env" "log" (func $log (param i32)))
+             (memory 1 1)
+             (func $sleeps (export "sleeps")
+               (call $sleep))
+             (func $calls_sleeps (export "calls_sleeps")
+               (call $sleeps))
+             (func $logs (export "logs")
+               (call $log (i32.const 1))))"#,
+    )?;
+
+    let mut asyncify = AsyncifyConfig::new();
+    asyncify
+        .import("env", "sleep")
+        .only("not_a_function_in_the_module");
+
+    let res = OptimizationOptions::new_optimize_for_size()
+        .asyncify(asyncify)
+        .run(&inpath, &outpath);
+
+    assert!(matches!(
+        res,
+        Err(OptimizationError::Asyncify {
+            source: AsyncifyError::UnknownFunction { .. }
+        })
+    ));
+
+    let mut asyncify = AsyncifyConfig::new();
+    asyncify.import("env", "sleep").remove("sleep");
+
+    let res = OptimizationOptions::new_optimize_for_size()
+        .asyncify(asyncify)
+        .run(&inpath, &outpath);
+
+    assert!(matches!(
+        res,
+        Err(OptimizationError::Asyncify {
+            source: AsyncifyError::ImportedFunction { .. }
+        })
+    ));
+
+    let mut asyncify = AsyncifyConfig::new();
+    asyncify.import("env", "sleep");
+
+    let report = OptimizationOptions::new_optimize_for_size()
+        .asyncify(asyncify)
+        .run_with_reports(&inpath, &outpath)?
+        .asyncify
+        .unwrap();
+
+    assert_eq!(report.instrumented, vec!["sleeps", "calls_sleeps"]);
+
+    Ok(())
+}
+
+#[test]
+fn detect_features_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (func $sign_ext (export "sign_ext") (param i32) (result i32)
+               (i32.extend8_s (local.get 0))))"#,
+    )?;
+
+    let detected = OptimizationOptions::new_opt_level_0().detect_features(&inpath)?;
+    assert_eq!(detected.used, vec![Feature::SignExt]);
+    assert!(detected.uses(Feature::SignExt));
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .mvp_features_only()
+        .run(&inpath, &outpath);
+    assert!(matches!(res, Err(OptimizationError::ValidateWasmInput)));
+
+    OptimizationOptions::new_opt_level_0()
+        .detect_feature_baseline()
+        .run(&inpath, &outpath)?;
+
+    // GC can't be enabled without reference types.
+    fs::write(
+        &inpath,
+        r#"(module
+             (type $point (struct (field i32)))
+             (func $new_point (export "new_point") (result i32)
+               (struct.get $point 0
+                 (struct.new $point (i32.const 1)))))"#,
+    )?;
+
+    let detected = OptimizationOptions::new_opt_level_0().detect_features(&inpath)?;
+    assert_eq!(detected.used, vec![Feature::ReferenceTypes, Feature::Gc]);
+
+    Ok(())
+}
+
+#[test]
+fn lower_unsupported_features_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (func $sign_ext (export "sign_ext") (param i32) (result i32)
+               (i32.extend8_s (local.get 0))))"#,
+    )?;
+
+    OptimizationOptions::new_opt_level_0()
+        .mvp_features_only()
+        .lower_unsupported_features(true)
+        .run(&inpath, &outpath)?;
+
+    let detected = OptimizationOptions::new_opt_level_0().detect_features(&outpath)?;
+    assert!(detected.used.is_empty());
+
+    fs::write(
+        &inpath,
+        r#"(module
+             (func $simd (export "simd") (result v128)
+               (v128.const i32x4 0 0 0 0)))"#,
+    )?;
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .mvp_features_only()
+        .lower_unsupported_features(true)
+        .run(&inpath, &outpath);
+
+    match res {
+        Err(OptimizationError::UnsupportedFeatures { unsupported }) => {
+            assert_eq!(unsupported.len(), 1);
+            assert_eq!(unsupported[0].feature, Feature::Simd);
+            assert_eq!(unsupported[0].functions, vec!["simd".to_string()]);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Stripping exceptions makes `throw` trap, so is opt-in.
+    fs::write(
+        &inpath,
+        r#"(module
+             (tag $error)
+             (func $fail (export "fail")
+               (throw $error)))"#,
+    )?;
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .mvp_features_only()
+        .lower_unsupported_features(true)
+        .run(&inpath, &outpath);
+
+    match res {
+        Err(OptimizationError::UnsupportedFeatures { unsupported }) => {
+            assert_eq!(unsupported.len(), 1);
+            assert_eq!(unsupported[0].feature, Feature::ExceptionHandling);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    OptimizationOptions::new_opt_level_0()
+        .mvp_features_only()
+        .lower_unsupported_features(true)
+        .strip_exceptions(true)
+        .run(&inpath, &outpath)?;
+
+    Ok(())
+}
+
+#[test]
+fn lowering_options_work() -> anyhow::Result<()> {
+    let (temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (memory 1 1)
+             (func $add (export "add") (param i64 i64) (result i64)
+               (i64.add (local.get 0) (local.get 1)))
+             (func $load (export "load") (param i32) (result i32)
+               (i32.load align=1 (local.get 0))))"#,
+    )?;
+
+    let checkpath = temp_dir.path().join("checkfile.wasm");
+
+    OptimizationOptions::new_opt_level_0()
+        .lower_i64_to_i32(true)
+        .lower_alignment(true)
+        .run(&inpath, &outpath)?;
+
+    let mut contract = ModuleContract::new();
+    contract.require_function_export("add", &[ValType::I32; 4], &[ValType::I32]);
+
+    OptimizationOptions::new_opt_level_0()
+        .contract(contract)
+        .run(&outpath, &checkpath)?;
+
+    Ok(())
+}
+
+#[test]
+fn audit_features_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, _outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (memory 1 1)
+             (func $plain (export "plain") (param i32) (result i32)
+               (i32.add (local.get 0) (i32.const 1)))
+             (func $simd (export "simd") (result i32)
+               (i32x4.extract_lane 0
+                 (i32x4.add (v128.const i32x4 1 2 3 4) (v128.const i32x4 1 2 3 4))))
+             (func $bulk (export "bulk")
+               (memory.fill (i32.const 0) (i32.const 0) (i32.const 16))
+               (drop (i32.extend8_s (i32.const 0)))))"#,
+    )?;
+
+    let audit = OptimizationOptions::new_opt_level_0().audit_features(&inpath)?;
+
+    let simd = audit.get(Feature::Simd).expect("simd");
+    assert_eq!(simd.functions.len(), 1);
+    assert_eq!(simd.functions[0].function, "simd");
+    assert_eq!(simd.functions[0].instructions, 4);
+    assert_eq!(simd.instructions(), 4);
+
+    let bulk = audit.get(Feature::BulkMemory).expect("bulk memory");
+    assert_eq!(bulk.functions.len(), 1);
+    assert_eq!(bulk.functions[0].function, "bulk");
+    assert_eq!(bulk.functions[0].instructions, 1);
+
+    let sign_ext = audit.get(Feature::SignExt).expect("sign ext");
+    assert_eq!(sign_ext.functions[0].function, "bulk");
+
+    assert!(audit.get(Feature::Atomics).is_none());
+    assert!(audit
+        .features
+        .iter()
+        .all(|usage| usage.functions.iter().all(|f| f.function != "plain")));
+
+    Ok(())
+}
+
+#[test]
+fn determinism_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (func $int (export "int") (param i32) (result i32)
+               (i32.add (local.get 0) (i32.const 1)))
+             (func $float_bits (export "float_bits") (param f32) (result i32)
+               (i32.reinterpret_f32 (f32.div (local.get 0) (local.get 0)))))"#,
+    )?;
+
+    let report = OptimizationOptions::new_opt_level_0()
+        .determinism(DeterminismConfig::new())
+        .run_with_reports(&inpath, &outpath)?
+        .determinism
+        .unwrap();
+
+    assert!(!report.is_deterministic());
+    assert!(report.violations.iter().all(|v| v.function == "float_bits"));
+    assert!(report
+        .violations
+        .iter()
+        .any(|v| v.kind == ViolationKind::Float));
+    assert!(report
+        .violations
+        .iter()
+        .any(|v| v.kind == ViolationKind::NanObservable && v.instructions == 1));
+
+    let mut determinism = DeterminismConfig::new();
+    determinism.allow_floats(true).canonicalize_nans(true);
+
+    let report = OptimizationOptions::new_opt_level_0()
+        .determinism(determinism)
+        .run_with_reports(&inpath, &outpath)?
+        .determinism
+        .unwrap();
+    assert!(report.is_deterministic());
+
+    let mut determinism = DeterminismConfig::new();
+    determinism.reject(true);
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .determinism(determinism)
+        .run(&inpath, &outpath);
+    assert!(matches!(
+        res,
+        Err(OptimizationError::Nondeterministic { .. })
+    ));
+
+    // DeNan doesn't canonicalize SIMD lanes.
+    fs::write(
+        &inpath,
+        r#"(module
+             (func $relaxed (export "relaxed") (param v128 v128) (result v128)
+               (i8x16.relaxed_swizzle (local.get 0) (local.get 1)))
+             (func $simd_float (export "simd_float") (param v128) (result v128)
+               (f32x4.sqrt (local.get 0))))"#,
+    )?;
+
+    let mut determinism = DeterminismConfig::new();
+    determinism.allow_floats(true).canonicalize_nans(true);
+
+    let report = OptimizationOptions::new_opt_level_0()
+        .all_features()
+        .determinism(determinism)
+        .run_with_reports(&inpath, &outpath)?
+        .determinism
+        .unwrap();
+
+    let violations: Vec<(&str, ViolationKind, u32)> = report
+        .violations
+        .iter()
+        .map(|v| (v.function.as_str(), v.kind, v.instructions))
+        .collect();
+    assert_eq!(
+        violations,
+        vec![
+            ("relaxed", ViolationKind::RelaxedSimd, 1),
+            ("simd_float", ViolationKind::SimdNan, 1),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn contract_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (import "env" "log" (func $log (param i32)))
+             (func $main (export "main") (param i32 i32) (result i32)
+               (call $log (local.get 0))
+               (i32.add (local.get 0) (local.get 1))))"#,
+    )?;
+
+    let mut contract = ModuleContract::new();
+    contract
+        .require_function_export("main", &[ValType::I32, ValType::I32], &[ValType::I32])
+        .allow_import("env", "*");
+
+    OptimizationOptions::new_opt_level_0()
+        .contract(contract.clone())
+        .run(&inpath, &outpath)?;
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .contract(contract)
+        .add_pass(Pass::MinifyImportsAndExports)
+        .run(&inpath, &outpath);
+
+    match res {
+        Err(OptimizationError::ContractViolated { stage, violations }) => {
+            assert_eq!(stage, ContractStage::Output);
+            assert_eq!(
+                violations,
+                vec![ContractViolation::MissingExport {
+                    name: "main".to_string()
+                }]
+            );
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let mut contract = ModuleContract::new();
+    contract
+        .require_function_export("main", &[ValType::I32], &[ValType::I32])
+        .restrict_imports();
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .contract(contract)
+        .run(&inpath, &outpath);
+
+    match res {
+        Err(OptimizationError::ContractViolated { stage, violations }) => {
+            assert_eq!(stage, ContractStage::Input);
+            assert_eq!(violations.len(), 2);
+            assert!(matches!(
+                violations[0],
+                ContractViolation::SignatureMismatch { .. }
+            ));
+            assert!(matches!(
+                &violations[1],
+                ContractViolation::DisallowedImport { module, base, .. }
+                    if module == "env" && base == "log"
+            ));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn rename_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (import "env" "log" (func $log (param i32)))
+             (import "env" "abort" (func $abort))
+             (func $start (export "_start")
+               (call $log (i32.const 0))
+               (call $abort))
+             (func $init (export "wasm_init")))"#,
+    )?;
+
+    let mut rename = RenameConfig::new();
+    rename
+        .rename_import("env", "log", "print")
+        .rename_import_module("env", "host")
+        .rename_export("_start", "main")
+        .rename_exports_matching(Regex::new("^wasm_(.*)$")?, "$1");
+
+    let mut contract = ModuleContract::new();
+    contract
+        .require_function_export("main", &[], &[])
+        .require_export("init")
+        .allow_import("host", "*");
+
+    let reports = OptimizationOptions::new_opt_level_0()
+        .rename(rename)
+        .contract(contract)
+        .determinism(DeterminismConfig::new())
+        .run_with_reports(&inpath, &outpath)?;
+
+    assert!(reports.asyncify.is_none());
+    assert!(reports.determinism.unwrap().is_deterministic());
+
+    let report = reports.rename.unwrap();
+
+    assert_eq!(report.imports.len(), 2);
+    assert_eq!(report.imports[0].old_base, "log");
+    assert_eq!(report.imports[0].new_module, "host");
+    assert_eq!(report.imports[0].new_base, "print");
+    assert_eq!(report.imports[1].new_module, "host");
+    assert_eq!(report.imports[1].new_base, "abort");
+    assert_eq!(
+        report.exports,
+        vec![
+            ExportRename {
+                old: "_start".to_string(),
+                new: "main".to_string(),
+            },
+            ExportRename {
+                old: "wasm_init".to_string(),
+                new: "init".to_string(),
+            },
+        ]
+    );
+
+    let mut rename = RenameConfig::new();
+    rename.rename_export("_start", "wasm_init");
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .rename(rename)
+        .run(&inpath, &outpath);
+    assert!(matches!(
+        res,
+        Err(OptimizationError::Rename {
+            source: RenameError::DuplicateExport { .. }
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn stub_imports_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (import "env" "abort" (func $abort))
+             (import "env" "get" (func $get (result i32)))
+             (import "env" "time" (func $time (result i64)))
+             (func $run (export "run") (result i32)
+               (drop (call $time))
+               (if (i32.eqz (call $get)) (then (call $abort)))
+               (call $get)))"#,
+    )?;
+
+    let mut stubs = ImportStubs::new();
+    stubs
+        .trap("env", "abort")
+        .constant("env", "get", StubValue::I32(42))
+        .zero("env", "*");
+
+    let mut contract = ModuleContract::new();
+    contract.restrict_imports();
+
+    OptimizationOptions::new_opt_level_0()
+        .stub_imports(stubs)
+        .contract(contract)
+        .run(&inpath, &outpath)?;
+
+    let mut stubs = ImportStubs::new();
+    stubs.constant("env", "get", StubValue::F32(1.0));
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .stub_imports(stubs)
+        .run(&inpath, &outpath);
+    assert!(matches!(
+        res,
+        Err(OptimizationError::StubImports {
+            source: StubError::TypeMismatch { .. }
+        })
+    ));
+
+    let mut stubs = ImportStubs::new();
+    stubs.trap("env", "missing");
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .stub_imports(stubs)
+        .run(&inpath, &outpath);
+    assert!(matches!(
+        res,
+        Err(OptimizationError::StubImports {
+            source: StubError::UnknownImport { .. }
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn metadce_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (import "env" "register" (func $register))
+             (import "env" "other" (func $other))
+             (func $main (export "main")
+               (call $register))
+             (func $handle_event (export "handle_event"))
+             (func $unused (export "unused")
+               (call $other)))"#,
+    )?;
+
+    let mut graph = MetaDceGraph::new();
+    graph.node("main").root().export("main");
+    graph.node("on_event").export("handle_event");
+    graph
+        .node("register")
+        .import("env", "register")
+        .reaches("on_event");
+    graph.node("other").import("env", "other");
+    graph.node("unused").export("unused");
+
+    let report = OptimizationOptions::new_opt_level_0()
+        .metadce(graph)
+        .run_with_reports(&inpath, &outpath)?
+        .metadce
+        .unwrap();
+
+    assert_eq!(report.unused_nodes, vec!["other", "unused"]);
+    assert_eq!(report.removed_exports, vec!["unused"]);
+    assert_eq!(report.removed_imports.len(), 1);
+    assert_eq!(report.removed_imports[0].base, "other");
+    assert_eq!(report.removed_functions, vec!["other", "unused"]);
+
+    let mut graph = MetaDceGraph::new();
+    graph.node("main").root().reaches("missing");
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .metadce(graph)
+        .run(&inpath, &outpath);
+    assert!(matches!(
+        res,
+        Err(OptimizationError::MetaDce {
+            source: MetaDceError::UnknownNode { .. }
+        })
+    ));
+
+    let mut graph = MetaDceGraph::new();
+    graph.node("main").root().export("main");
+    graph.node("missing").import("env", "missing");
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .metadce(graph)
+        .run(&inpath, &outpath);
+    assert!(matches!(
+        res,
+        Err(OptimizationError::MetaDce {
+            source: MetaDceError::UnknownImport { .. }
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn export_allowlist_works() -> anyhow::Result<()> {
+    let (_temp_dir, inpath, outpath) = write_fixture(
+        "wat",
+        r#"(module
+             (func $main (export "main") (result i32)
+               (i32.const 1))
+             (func $internal (export "internal") (result i32)
+               (i32.const 2))
+             (func $helper (export "helper") (result i32)
+               (i32.const 3)))"#,
+    )?;
+
+    let removed = OptimizationOptions::new_optimize_for_size()
+        .export_allowlist(&["main"])
+        .run_with_reports(&inpath, &outpath)?
+        .removed_exports
+        .unwrap();
+
+    assert_eq!(removed, vec!["internal", "helper"]);
+
+    let mut contract = ModuleContract::new();
+    contract.require_export("internal");
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .export_allowlist(&["main"])
+        .contract(contract)
+        .run(&inpath, &outpath);
+
+    match res {
+        Err(OptimizationError::ContractViolated { stage, .. }) => {
+            assert_eq!(stage, ContractStage::Output);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let res = OptimizationOptions::new_opt_level_0()
+        .export_allowlist(&["main", "missing"])
+        .run(&inpath, &outpath);
+
+    match res {
+        Err(OptimizationError::UnknownAllowlistExports { names }) => {
+            assert_eq!(names, vec!["missing"]);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Graph nodes may name pruned exports.
+    let mut graph = MetaDceGraph::new();
+    graph.node("main").root().export("main").reaches("internal");
+    graph.node("internal").export("internal");
+    graph.node("helper").export("helper");
+
+    let reports = OptimizationOptions::new_opt_level_0()
+        .export_allowlist(&["main", "helper"])
+        .metadce(graph)
+        .run_with_reports(&inpath, &outpath)?;
+
+    assert_eq!(reports.removed_exports.unwrap(), vec!["internal"]);
+    let report = reports.metadce.unwrap();
+    assert_eq!(report.unused_nodes, vec!["helper"]);
+    assert_eq!(report.removed_exports, vec!["helper"]);
+
+    Ok(())
+}
+
+#[test]
+fn merge_works() -> anyhow::Result<()> {
+    let temp_dir = Builder::new().prefix("wasm_opt_tests").tempdir()?;
+    let app_path = temp_dir.path().join("app.wat");
+    let lib_path = temp_dir.path().join("lib.wat");
+    let outpath = temp_dir.path().join("outfile.wasm");
+
+    fs::write(
+        &app_path,
+        r#"(module
+             (import "lib" "add" (func $add (param i32 i32) (result i32)))
+             (import "env" "log" (func $log (param i32)))
+             (func $main (export "main") (result i32)
+               (call $log (i32.const 0))
+               (call $add (i32.const 1) (i32.const 2))))"#,
+    )?;
+    fs::write(
+        &lib_path,
+        r#"(module
+             (func $add (export "add") (param i32 i32) (result i32)
+               (i32.add (local.get 0) (local.get 1)))
+             (func $main (export "main") (result i32)
+               (i32.const 0)))"#,
+    )?;
+
+    let res = merge_files(&[("app", &app_path), ("lib", &lib_path)]);
+    assert!(matches!(
+        res,
+        Err(MergeError::ExportConflict { ref export, .. }) if export == "main"
+    ));
+
+    let mut modules = Vec::new();
+    for (name, path) in &[("app", &app_path), ("lib", &lib_path)] {
+        let mut m = Module::new();
+        ModuleReader::new().read(path, &mut m, None)?;
+        modules.push((*name, m));
+    }
+
+    let merged = MergeOptions::new()
+        .export_conflicts(ExportConflicts::Skip)
+        .merge(&modules)?;
+
+    let report = merged.report();
+    assert_eq!(report.resolved.len(), 1);
+    assert_eq!(report.resolved[0].module, "lib");
+    assert_eq!(report.resolved[0].base, "add");
+    assert_eq!(report.unresolved.len(), 1);
+    assert_eq!(report.unresolved[0].base, "log");
+
+    let mut contract = ModuleContract::new();
+    contract
+        .require_function_export("main", &[], &[ValType::I32])
+        .allow_import("env", "log")
+        .restrict_imports();
+
+    OptimizationOptions::new_optimize_for_size()
+        .contract(contract)
+        .run_merged(merged, &outpath)?;
+
+    Ok(())
+}
@@ -223,6 +223,33 @@ fn pass_runner_works() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn pass_runner_default_phases_work() -> anyhow::Result<()> {
+    let temp_dir = Builder::new().prefix("wasm_opt_tests").tempdir()?;
+    let path = temp_dir.path().join("hello_world.wasm");
+
+    let temp_file = File::create(&path)?;
+    let mut buf_writer = BufWriter::new(&temp_file);
+    buf_writer.write_all(WASM_FILE)?;
+    drop(buf_writer);
+
+    let mut m = Module::new();
+    let mut reader = ModuleReader::new();
+    reader.read_binary(&path, &mut m, None)?;
+
+    let mut pass_runner = PassRunner::new(&mut m);
+    pass_runner.add_default_global_optimization_pre_passes();
+    pass_runner.add("asyncify");
+    pass_runner.add_default_function_optimization_passes();
+    pass_runner.add_default_global_optimization_post_passes();
+    pass_runner.run();
+    drop(pass_runner);
+
+    assert!(validate_wasm(&mut m));
+
+    Ok(())
+}
+
 #[test]
 fn pass_options_works() -> anyhow::Result<()> {
     let temp_dir = Builder::new().prefix("wasm_opt_tests").tempdir()?;
@@ -368,18 +395,31 @@ fn get_pass_description_valid_name_works() -> anyhow::Result<()> {
     let pass_description = pass_registry::get_pass_description("limit-segments");
 
     assert_eq!(
-        pass_description,
-        "attempt to merge segments to fit within web limits"
+        pass_description.as_deref(),
+        Some("attempt to merge segments to fit within web limits")
     );
 
     Ok(())
 }
 
+#[test]
+fn get_pass_description_invalid_name_works() -> anyhow::Result<()> {
+    let pass_description = pass_registry::get_pass_description("not-a-pass");
+
+    assert_eq!(pass_description, None);
+
+    Ok(())
+}
+
 #[test]
 fn is_pass_hidden_works() -> anyhow::Result<()> {
     let is_pass_hidden = pass_registry::is_pass_hidden("limit-segments");
 
-    assert_eq!(is_pass_hidden, false);
+    assert_eq!(is_pass_hidden, Some(false));
+
+    let is_pass_hidden = pass_registry::is_pass_hidden("not-a-pass");
+
+    assert_eq!(is_pass_hidden, None);
 
     Ok(())
 }